@@ -0,0 +1,63 @@
+#![feature(test)]
+
+extern crate test;
+
+use sanitizers::asan;
+use std::hint::black_box;
+use std::os::raw::c_void;
+use test::Bencher;
+
+/// Benchmarks checking if each of 64 addresses is poisoned using the runtime.
+#[bench]
+fn is_address_poisoned(b: &mut Bencher) {
+    let data = Box::new([0u8; 64]);
+    b.iter(|| {
+        black_box(&data[..])
+            .iter()
+            .filter(|byte| unsafe {
+                asan::is_address_poisoned(*byte as *const u8 as *const c_void)
+            })
+            .count()
+    });
+}
+
+/// Benchmarks checking if each of 64 addresses is poisoned by reading shadow
+/// memory.
+#[bench]
+fn fast_is_poisoned(b: &mut Bencher) {
+    let data = Box::new([0u8; 64]);
+    b.iter(|| {
+        black_box(&data[..])
+            .iter()
+            .filter(|byte| unsafe { asan::fast::is_poisoned(*byte as *const u8 as *const c_void) })
+            .count()
+    });
+}
+
+/// Benchmarks checking if a small region is poisoned using the runtime.
+#[bench]
+fn region_is_poisoned_64(b: &mut Bencher) {
+    let data = Box::new([0u8; 64]);
+    b.iter(|| asan::region_is_poisoned_val(black_box(&data[..])));
+}
+
+/// Benchmarks checking if a small region is poisoned by reading shadow memory.
+#[bench]
+fn fast_check_range_64(b: &mut Bencher) {
+    let data = Box::new([0u8; 64]);
+    b.iter(|| asan::fast::check_range(black_box(&data[..])));
+}
+
+/// Benchmarks checking if a large region is poisoned using the runtime.
+#[bench]
+fn region_is_poisoned_4096(b: &mut Bencher) {
+    let data = vec![0u8; 4096];
+    b.iter(|| asan::region_is_poisoned_val(black_box(&data[..])));
+}
+
+/// Benchmarks checking if a large region is poisoned by reading shadow memory.
+#[bench]
+fn fast_check_range_4096(b: &mut Bencher) {
+    let data = vec![0u8; 4096];
+    b.iter(|| asan::fast::check_range(black_box(&data[..])));
+}
@@ -0,0 +1,26 @@
+//! Build script for the `sanitizers` crate.
+//!
+//! Translates the sanitizers enabled for the current build (i.e., the values of
+//! the unstable `cfg(sanitize = "...")` option, which Cargo exposes to build
+//! scripts as `CARGO_CFG_SANITIZE`) into `sanitize_*` configuration options, so
+//! the crate can select between the sanitizer runtime interfaces and their
+//! no-op fallbacks without requiring the `cfg_sanitize` feature.
+use std::env;
+
+const SANITIZERS: &[&str] = &["address", "dataflow", "leak", "memory", "thread"];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=CARGO_CFG_SANITIZE");
+
+    for sanitizer in SANITIZERS {
+        println!("cargo:rustc-check-cfg=cfg(sanitize_{})", sanitizer);
+    }
+
+    let enabled = env::var("CARGO_CFG_SANITIZE").unwrap_or_default();
+    for sanitizer in enabled.split(',').map(str::trim) {
+        if SANITIZERS.contains(&sanitizer) {
+            println!("cargo:rustc-cfg=sanitize_{}", sanitizer);
+        }
+    }
+}
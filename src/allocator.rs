@@ -0,0 +1,103 @@
+use crate::ffi::allocator::*;
+
+use std::os::raw::c_void;
+
+/// Returns the number of bytes allocated and not yet freed by the application.
+pub fn current_allocated_bytes() -> usize {
+    unsafe { __sanitizer_get_current_allocated_bytes() }
+}
+
+/// Returns the number of bytes mapped by the allocator to fulfill allocation
+/// requests (i.e., including the chunks in the free lists and quarantine).
+pub fn heap_size() -> usize {
+    unsafe { __sanitizer_get_heap_size() }
+}
+
+/// Returns the number of bytes mapped by the allocator that can be used to
+/// fulfill allocation requests (i.e., freed chunks count toward it once they
+/// leave the quarantine).
+pub fn free_bytes() -> usize {
+    unsafe { __sanitizer_get_free_bytes() }
+}
+
+/// Returns the number of bytes in unmapped pages released to the OS.
+pub fn unmapped_bytes() -> usize {
+    unsafe { __sanitizer_get_unmapped_bytes() }
+}
+
+/// Returns the estimated number of bytes the allocator reserves for an
+/// allocation request of the given size.
+pub fn estimated_allocated_size(size: usize) -> usize {
+    unsafe { __sanitizer_get_estimated_allocated_size(size) }
+}
+
+/// Returns whether the given address is the beginning of a heap chunk allocated
+/// by the allocator and not yet freed (some runtimes, e.g., LeakSanitizer, also
+/// accept addresses inside the heap chunk).
+///
+/// # Safety
+///
+/// `ptr` must be an address in the application memory.
+pub unsafe fn get_ownership(ptr: *const c_void) -> bool {
+    unsafe { __sanitizer_get_ownership(ptr) != 0 }
+}
+
+/// Returns whether the given value is the beginning of a heap chunk allocated by
+/// the allocator (e.g., the value of a `Box`).
+pub fn get_ownership_val<T: ?Sized>(value: &T) -> bool {
+    unsafe { get_ownership(value as *const T as *const c_void) }
+}
+
+/// Returns the number of bytes reserved for the heap chunk that begins at the
+/// given address, or `None` if the address is not the beginning of a heap chunk
+/// allocated by the allocator and not yet freed (see `get_ownership`).
+///
+/// # Safety
+///
+/// `ptr` must be an address in the application memory.
+pub unsafe fn get_allocated_size(ptr: *const c_void) -> Option<usize> {
+    // The runtime reports an error for addresses it does not own.
+    if !unsafe { get_ownership(ptr) } {
+        return None;
+    }
+    Some(unsafe { __sanitizer_get_allocated_size(ptr) })
+}
+
+/// Returns the number of bytes reserved for the heap chunk that begins at the
+/// given value (e.g., the value of a `Box`), or `None` if the value is not the
+/// beginning of a heap chunk allocated by the allocator.
+pub fn get_allocated_size_val<T: ?Sized>(value: &T) -> Option<usize> {
+    unsafe { get_allocated_size(value as *const T as *const c_void) }
+}
+
+/// Returns the beginning of the heap chunk that contains the given address, or
+/// `None` if the address does not belong to a heap chunk allocated by the
+/// allocator and not yet freed.
+///
+/// # Safety
+///
+/// `ptr` must be an address in the application memory.
+pub unsafe fn get_allocated_begin(ptr: *const c_void) -> Option<*const c_void> {
+    let begin = unsafe { __sanitizer_get_allocated_begin(ptr) };
+    if begin.is_null() {
+        None
+    } else {
+        Some(begin)
+    }
+}
+
+/// Returns the beginning of the heap chunk that contains the given value, or
+/// `None` if the value does not belong to a heap chunk allocated by the
+/// allocator.
+pub fn get_allocated_begin_val<T: ?Sized>(value: &T) -> Option<*const c_void> {
+    unsafe { get_allocated_begin(value as *const T as *const c_void) }
+}
+
+/// Drains the allocator quarantines, returns the freed memory to the OS, and
+/// releases other non-essential allocator resources to reduce the RSS of the
+/// process. Currently, it has an effect with AddressSanitizer only.
+pub fn purge_allocator() {
+    unsafe {
+        __sanitizer_purge_allocator();
+    }
+}
@@ -1,33 +1,99 @@
-/// AddressSanitizer interface.
-///
-/// For more information about AddressSanitizer, see
-/// https://clang.llvm.org/docs/AddressSanitizer.html.
+use crate::common;
 use crate::ffi::asan::*;
 
 use std::ffi::CStr;
-use std::os::raw::c_void;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::size_of_val;
+use std::ops::Range;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Bump arena.
+///
+/// Objects allocated from an arena are not freed individually, but all at once
+/// when the arena is reset, so AddressSanitizer cannot detect uses after reset
+/// or overflows into the next object by itself. `AsanArena` poisons all the
+/// memory of its chunk that is not handed out.
+mod arena;
+/// Contiguous container annotations.
+///
+/// For more information about the container overflow detection, see
+/// <https://github.com/google/sanitizers/wiki/AddressSanitizerContainerOverflow>.
+mod container;
+/// Inline AddressSanitizer poison checks.
+///
+/// Reads the shadow memory directly (using the shadow mapping the compiler uses
+/// for the target, or the one queried once from the runtime on other targets)
+/// instead of calling into the runtime for every check, which makes
+/// the checks cheap enough for hot paths (e.g., debug assertions in
+/// allocators). The results are the same as those of
+/// `asan::is_address_poisoned` and `asan::region_is_poisoned`.
+pub mod fast;
+/// Object pool.
+///
+/// The memory of the objects of a pool is never freed while the pool is alive,
+/// so AddressSanitizer cannot detect uses of an object after it is returned to
+/// the pool by itself. `PoisonedPool` poisons the objects while they are in the
+/// pool.
+mod pool;
+/// Redzone allocator.
+///
+/// AddressSanitizer only detects overflows and uses after free of memory
+/// allocated by its own allocator (i.e., through `malloc` and friends). Memory
+/// allocated by other allocators (e.g., jemalloc or mimalloc) can be protected
+/// by wrapping them in a `RedzoneAllocator`.
+mod redzone;
+
+pub use crate::common::StackTrace;
+
+pub use self::arena::*;
+pub use self::container::*;
+pub use self::pool::*;
+pub use self::redzone::*;
 
 /// Marks a memory region as unaddressable.
-pub fn poison_memory_region(addr: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `addr` must point to a memory region of at least `size` bytes that was
+/// previously allocated by the program.
+pub unsafe fn poison_memory_region(addr: *const c_void, size: usize) {
     unsafe {
         __asan_poison_memory_region(addr, size);
     }
 }
 
 /// Marks a memory region as addressable.
-pub fn unpoison_memory_region(addr: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `addr` must point to a memory region of at least `size` bytes that was
+/// previously allocated by the program.
+pub unsafe fn unpoison_memory_region(addr: *const c_void, size: usize) {
     unsafe {
         __asan_unpoison_memory_region(addr, size);
     }
 }
 
 /// Checks if an address is poisoned.
-pub fn is_address_poisoned(addr: *const c_void) -> bool {
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+pub unsafe fn is_address_poisoned(addr: *const c_void) -> bool {
     unsafe { __asan_address_is_poisoned(addr) != 0 }
 }
 
 /// Checks if a region is poisoned and returns the address of the first poisoned byte.
-pub fn region_is_poisoned(beg: *mut c_void, size: usize) -> Option<*mut c_void> {
+///
+/// # Safety
+///
+/// `beg` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn region_is_poisoned(beg: *mut c_void, size: usize) -> Option<*mut c_void> {
     let addr = unsafe { __asan_region_is_poisoned(beg, size) };
     if addr.is_null() {
         None
@@ -37,12 +103,243 @@ pub fn region_is_poisoned(beg: *mut c_void, size: usize) -> Option<*mut c_void>
 }
 
 /// Describes an address (useful for calling from the debugger).
-pub fn describe_address(addr: *mut c_void) {
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+pub unsafe fn describe_address(addr: *mut c_void) {
     unsafe {
         __asan_describe_address(addr);
     }
 }
 
+/// Marks the memory of `value` (e.g., a slice) as unaddressable.
+pub fn poison_val<T: ?Sized>(value: &T) {
+    unsafe {
+        poison_memory_region(value as *const T as *const c_void, size_of_val(value));
+    }
+}
+
+/// Marks the memory of `value` (e.g., a slice) as addressable.
+pub fn unpoison_val<T: ?Sized>(value: &T) {
+    unsafe {
+        unpoison_memory_region(value as *const T as *const c_void, size_of_val(value));
+    }
+}
+
+/// Checks if the memory of `value` is poisoned and returns the offset of the
+/// first poisoned byte.
+pub fn region_is_poisoned_val<T: ?Sized>(value: &T) -> Option<usize> {
+    let beg = value as *const T as *mut c_void;
+    unsafe { region_is_poisoned(beg, size_of_val(value)) }.map(|addr| addr as usize - beg as usize)
+}
+
+/// Describes the address of `value`.
+pub fn describe_val<T: ?Sized>(value: &T) {
+    unsafe {
+        describe_address(value as *const T as *mut c_void);
+    }
+}
+
+/// Kind of memory an address belongs to, as reported by `locate_address`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AsanLocationKind {
+    /// Heap memory (i.e., a chunk allocated by the ASan allocator).
+    Heap,
+    /// Memory that is not known to ASan (e.g., a wild or dangling pointer).
+    HeapInvalid,
+    /// Stack memory.
+    Stack,
+    /// Fake stack memory (see `detect_stack_use_after_return`).
+    FakeStack,
+    /// Global variable memory.
+    Global,
+    /// Shadow memory for the low application memory.
+    LowShadow,
+    /// Unmapped memory between the low and high shadow memory.
+    ShadowGap,
+    /// Shadow memory for the high application memory.
+    HighShadow,
+    /// Low application memory.
+    LowMemory,
+    /// High application memory.
+    HighMemory,
+    /// A category not known to this crate.
+    Unknown(String),
+}
+
+impl AsanLocationKind {
+    fn from_category(category: &str) -> Self {
+        match category {
+            "heap" => AsanLocationKind::Heap,
+            "heap-invalid" => AsanLocationKind::HeapInvalid,
+            "stack" => AsanLocationKind::Stack,
+            "stack-fake" => AsanLocationKind::FakeStack,
+            "global" => AsanLocationKind::Global,
+            "low shadow" | "shadow-low" => AsanLocationKind::LowShadow,
+            "shadow gap" | "shadow-gap" => AsanLocationKind::ShadowGap,
+            "high shadow" | "shadow-high" => AsanLocationKind::HighShadow,
+            "low memory" => AsanLocationKind::LowMemory,
+            "high memory" => AsanLocationKind::HighMemory,
+            _ => AsanLocationKind::Unknown(category.to_owned()),
+        }
+    }
+}
+
+/// Struct to hold information about an address, as returned by
+/// `locate_address`.
+#[derive(Clone, Debug)]
+pub struct AsanLocation {
+    /// Kind of memory the address belongs to.
+    pub kind: AsanLocationKind,
+    /// Name of the variable, if the address belongs to a global or stack
+    /// variable.
+    pub name: Option<String>,
+    /// Start of the containing region (i.e., the variable or heap chunk), or
+    /// null if unknown.
+    pub region_address: *mut c_void,
+    /// Size of the containing region in bytes, or 0 if unknown.
+    pub region_size: usize,
+}
+
+/// Gets information about an address (e.g., the kind of memory it belongs to and
+/// the variable or heap chunk that contains it).
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+pub unsafe fn locate_address(addr: *const c_void) -> AsanLocation {
+    const BUFFER_SIZE: usize = 1024;
+    let mut name = vec![0 as c_char; BUFFER_SIZE];
+    let mut region_address: *mut c_void = std::ptr::null_mut();
+    let mut region_size: usize = 0;
+
+    unsafe {
+        let category = __asan_locate_address(
+            addr as *mut c_void,
+            name.as_mut_ptr(),
+            BUFFER_SIZE,
+            &mut region_address,
+            &mut region_size,
+        );
+        let kind = if category.is_null() {
+            AsanLocationKind::Unknown(String::new())
+        } else {
+            AsanLocationKind::from_category(&CStr::from_ptr(category).to_string_lossy())
+        };
+        let name = CStr::from_ptr(name.as_ptr()).to_string_lossy().into_owned();
+
+        AsanLocation {
+            kind,
+            name: if name.is_empty() { None } else { Some(name) },
+            region_address,
+            region_size,
+        }
+    }
+}
+
+/// Gets information about the address of `value`.
+pub fn locate_val<T: ?Sized>(value: &T) -> AsanLocation {
+    unsafe { locate_address(value as *const T as *const c_void) }
+}
+
+/// Struct to hold the stack trace and thread ID of the allocation or
+/// deallocation of a heap chunk.
+#[derive(Clone, Debug)]
+pub struct AsanHeapStack {
+    /// Stack trace of the allocation or deallocation.
+    pub trace: StackTrace,
+    /// ID of the thread that allocated or deallocated the heap chunk.
+    pub thread_id: c_int,
+}
+
+fn get_heap_stack<F>(get_stack: F, addr: *const c_void) -> Option<AsanHeapStack>
+where
+    F: FnOnce(*mut c_void, *mut *mut c_void, usize, *mut c_int) -> usize,
+{
+    // Stack traces stored by the ASan allocator have at most 255 frames.
+    const MAX_FRAMES: usize = 256;
+    let mut frames: Vec<*mut c_void> = vec![std::ptr::null_mut(); MAX_FRAMES];
+    let mut thread_id: c_int = 0;
+
+    let count = get_stack(
+        addr as *mut c_void,
+        frames.as_mut_ptr(),
+        MAX_FRAMES,
+        &mut thread_id,
+    );
+    if count == 0 {
+        return None;
+    }
+    frames.truncate(count);
+
+    Some(AsanHeapStack {
+        trace: StackTrace::new(frames),
+        thread_id,
+    })
+}
+
+/// Gets the allocation stack trace and thread ID for a heap address, or `None`
+/// if the address does not belong to a heap chunk.
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+pub unsafe fn alloc_stack(addr: *const c_void) -> Option<AsanHeapStack> {
+    get_heap_stack(
+        |addr, trace, size, thread_id| unsafe {
+            __asan_get_alloc_stack(addr, trace, size, thread_id)
+        },
+        addr,
+    )
+}
+
+/// Gets the deallocation stack trace and thread ID for a heap address, or `None`
+/// if the address does not belong to a freed heap chunk (e.g., it has not been
+/// freed yet, or it has been evicted from the quarantine).
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+pub unsafe fn free_stack(addr: *const c_void) -> Option<AsanHeapStack> {
+    get_heap_stack(
+        |addr, trace, size, thread_id| unsafe {
+            __asan_get_free_stack(addr, trace, size, thread_id)
+        },
+        addr,
+    )
+}
+
+/// Gets the allocation stack trace and thread ID for the heap chunk that
+/// contains `value`, or `None` if `value` does not belong to a heap chunk.
+pub fn alloc_stack_val<T: ?Sized>(value: &T) -> Option<AsanHeapStack> {
+    unsafe { alloc_stack(value as *const T as *const c_void) }
+}
+
+/// Updates the allocation stack trace of the heap chunk that contains the given
+/// address to the current stack trace (e.g., when an object is handed out by a
+/// pool or a custom allocator), so that reports point at the current call site
+/// instead of the original allocation.
+///
+/// Returns `false` if the address does not belong to a heap chunk allocated by
+/// the ASan allocator.
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+pub unsafe fn update_allocation_context(addr: *const c_void) -> bool {
+    unsafe { __asan_update_allocation_context(addr as *mut c_void) != 0 }
+}
+
+/// Updates the allocation stack trace of the heap chunk that contains `value`
+/// to the current stack trace.
+///
+/// Returns `false` if `value` does not belong to a heap chunk allocated by the
+/// ASan allocator.
+pub fn update_allocation_context_val<T: ?Sized>(value: &T) -> bool {
+    unsafe { update_allocation_context(value as *const T as *const c_void) }
+}
+
 /// Checks if an error has been or is being reported.
 pub fn report_present() -> bool {
     unsafe { __asan_report_present() != 0 }
@@ -90,13 +387,334 @@ pub fn get_report_description() -> String {
     }
 }
 
+/// Kind of memory access of an ASan error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+impl AccessKind {
+    /// Converts an access type as returned by `get_report_access_type` (0 =
+    /// read, 1 = write).
+    pub fn from_access_type(access_type: i32) -> Self {
+        if access_type != 0 {
+            AccessKind::Write
+        } else {
+            AccessKind::Read
+        }
+    }
+
+    /// Returns whether the access is a write.
+    pub fn is_write(self) -> bool {
+        self == AccessKind::Write
+    }
+}
+
+/// Reports an ASan error for an access of `size` bytes at `addr`, as if it was
+/// detected by the instrumentation at the given PC (program counter), BP (base
+/// pointer), and SP (stack pointer) register values.
+///
+/// The program is aborted after the error is reported unless `halt_on_error` is
+/// disabled.
+///
+/// # Safety
+///
+/// `pc`, `bp`, and `sp` must be register values of a frame of the current
+/// thread that is still alive (or null), because the stack trace of the report
+/// is unwound from them.
+pub unsafe fn report_error(
+    pc: *const c_void,
+    bp: *const c_void,
+    sp: *const c_void,
+    addr: *const c_void,
+    kind: AccessKind,
+    size: usize,
+) {
+    unsafe {
+        __asan_report_error(
+            pc as *mut c_void,
+            bp as *mut c_void,
+            sp as *mut c_void,
+            addr as *mut c_void,
+            kind.is_write() as c_int,
+            size,
+        );
+    }
+}
+
+/// Reports an ASan error for an access of `len` bytes at `ptr` (e.g., when a
+/// hand-written bounds check fails), with the stack trace of the caller.
+///
+/// The address of the error is the first poisoned byte of the accessed memory,
+/// or `ptr` if none of it is poisoned. The program is aborted after the error
+/// is reported unless `halt_on_error` is disabled.
+///
+/// # Safety
+///
+/// The `len` bytes at `ptr` must be in the application memory.
+#[inline(always)]
+pub unsafe fn report_bad_access(ptr: *const c_void, len: usize, kind: AccessKind) {
+    let addr = unsafe { region_is_poisoned(ptr as *mut c_void, len) }
+        .map_or(ptr, |addr| addr as *const c_void);
+    let (pc, bp, sp) = caller_registers();
+    unsafe { report_error(pc, bp, sp, addr, kind, len) };
+}
+
+/// Returns the PC (program counter), BP (base pointer), and SP (stack pointer)
+/// register values of the frame it is inlined into, or null where not
+/// available.
+#[inline(always)]
+fn caller_registers() -> (*const c_void, *const c_void, *const c_void) {
+    #[allow(unused_mut, unused_assignments)]
+    let (mut pc, mut bp, mut sp) = (
+        std::ptr::null::<c_void>(),
+        std::ptr::null::<c_void>(),
+        std::ptr::null::<c_void>(),
+    );
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        std::arch::asm!(
+            "lea {pc}, [rip]",
+            "mov {bp}, rbp",
+            "mov {sp}, rsp",
+            pc = out(reg) pc,
+            bp = out(reg) bp,
+            sp = out(reg) sp,
+            options(nomem, nostack, preserves_flags),
+        );
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        std::arch::asm!(
+            "adr {pc}, .",
+            "mov {bp}, x29",
+            "mov {sp}, sp",
+            pc = out(reg) pc,
+            bp = out(reg) bp,
+            sp = out(reg) sp,
+            options(nomem, nostack, preserves_flags),
+        );
+    }
+    #[cfg(target_arch = "riscv64")]
+    unsafe {
+        std::arch::asm!(
+            "auipc {pc}, 0",
+            "mv {bp}, s0",
+            "mv {sp}, sp",
+            pc = out(reg) pc,
+            bp = out(reg) bp,
+            sp = out(reg) sp,
+            options(nomem, nostack, preserves_flags),
+        );
+    }
+    (pc, bp, sp)
+}
+
+/// Bug type of an ASan error.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BugType {
+    HeapBufferOverflow,
+    StackBufferOverflow,
+    StackBufferUnderflow,
+    DynamicStackBufferOverflow,
+    GlobalBufferOverflow,
+    IntraObjectOverflow,
+    ContainerOverflow,
+    HeapUseAfterFree,
+    StackUseAfterReturn,
+    StackUseAfterScope,
+    UseAfterPoison,
+    InitializationOrderFiasco,
+    DoubleFree,
+    BadFree,
+    AllocDeallocMismatch,
+    NewDeleteTypeMismatch,
+    MemcpyParamOverlap,
+    StackOverflow,
+    WildAddress,
+    UnknownCrash,
+    /// A bug type not known to this crate.
+    Unknown(String),
+}
+
+impl BugType {
+    // Ordered so that descriptions are matched against longer names first
+    // (e.g., "dynamic-stack-buffer-overflow" before "stack-buffer-overflow").
+    const NAMES: &'static [(&'static str, BugType)] = &[
+        (
+            "dynamic-stack-buffer-overflow",
+            BugType::DynamicStackBufferOverflow,
+        ),
+        (
+            "initialization-order-fiasco",
+            BugType::InitializationOrderFiasco,
+        ),
+        ("new-delete-type-mismatch", BugType::NewDeleteTypeMismatch),
+        ("alloc-dealloc-mismatch", BugType::AllocDeallocMismatch),
+        ("global-buffer-overflow", BugType::GlobalBufferOverflow),
+        ("stack-buffer-underflow", BugType::StackBufferUnderflow),
+        ("stack-use-after-return", BugType::StackUseAfterReturn),
+        ("heap-buffer-overflow", BugType::HeapBufferOverflow),
+        ("intra-object-overflow", BugType::IntraObjectOverflow),
+        ("memcpy-param-overlap", BugType::MemcpyParamOverlap),
+        ("stack-buffer-overflow", BugType::StackBufferOverflow),
+        ("stack-use-after-scope", BugType::StackUseAfterScope),
+        ("heap-use-after-free", BugType::HeapUseAfterFree),
+        ("container-overflow", BugType::ContainerOverflow),
+        ("use-after-poison", BugType::UseAfterPoison),
+        ("stack-overflow", BugType::StackOverflow),
+        ("unknown-crash", BugType::UnknownCrash),
+        ("double-free", BugType::DoubleFree),
+        ("wild-addr", BugType::WildAddress),
+        ("bad-free", BugType::BadFree),
+    ];
+
+    /// Parses the bug description of an ASan error (e.g., "heap-use-after-free"
+    /// or "8-byte-read-heap-buffer-overflow").
+    pub fn from_description(description: &str) -> Self {
+        Self::NAMES
+            .iter()
+            .find(|(name, _)| {
+                description == *name
+                    || description.ends_with(&format!("-{}", name))
+                    || description.starts_with(&format!("{}-", name))
+            })
+            .map_or_else(
+                || BugType::Unknown(description.to_owned()),
+                |(_, bug_type)| bug_type.clone(),
+            )
+    }
+}
+
+/// Struct to hold the report data of an ASan error.
+#[derive(Clone, Debug)]
+pub struct AsanReport {
+    pub pc: *mut c_void,
+    pub bp: *mut c_void,
+    pub sp: *mut c_void,
+    pub address: *mut c_void,
+    pub access_kind: AccessKind,
+    pub access_size: usize,
+    pub bug_type: BugType,
+    pub description: String,
+}
+
+/// Returns the report data of the ASan error that has been or is being
+/// reported, if any.
+pub fn current_report() -> Option<AsanReport> {
+    if !report_present() {
+        return None;
+    }
+
+    let description = get_report_description();
+    Some(AsanReport {
+        pc: get_report_pc(),
+        bp: get_report_bp(),
+        sp: get_report_sp(),
+        address: get_report_address(),
+        access_kind: AccessKind::from_access_type(get_report_access_type()),
+        access_size: get_report_access_size(),
+        bug_type: BugType::from_description(&description),
+        description,
+    })
+}
+
 /// Sets a callback function to be called during ASan error reporting.
-pub fn set_error_report_callback(callback: Option<unsafe extern "C" fn(arg1: *const i8)>) {
+pub fn set_error_report_callback(callback: Option<unsafe extern "C" fn(arg1: *const c_char)>) {
     unsafe {
         __asan_set_error_report_callback(callback);
     }
 }
 
+type ErrorReportCallback = dyn Fn(&str) + Send + Sync;
+
+static ERROR_REPORT_CALLBACKS: Mutex<Vec<(usize, Arc<ErrorReportCallback>)>> =
+    Mutex::new(Vec::new());
+static NEXT_ERROR_REPORT_CALLBACK_ID: AtomicUsize = AtomicUsize::new(0);
+
+unsafe extern "C" fn error_report_callback(report: *const c_char) {
+    let report = if report.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(report).to_string_lossy().into_owned() }
+    };
+    // Callbacks are called without holding the lock so they can register or
+    // unregister callbacks themselves.
+    let callbacks: Vec<Arc<ErrorReportCallback>> = ERROR_REPORT_CALLBACKS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .map(|(_, callback)| callback.clone())
+        .collect();
+    for callback in callbacks {
+        // Unwinding into the runtime is not allowed.
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| callback(&report)));
+    }
+}
+
+/// Handle to a callback registered with `on_error_report`.
+///
+/// Dropping the handle does not unregister the callback; use
+/// `ErrorReportHandle::unregister` instead.
+#[derive(Debug)]
+pub struct ErrorReportHandle {
+    id: usize,
+}
+
+impl ErrorReportHandle {
+    /// Unregisters the callback.
+    pub fn unregister(self) {
+        ERROR_REPORT_CALLBACKS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|(id, _)| *id != self.id);
+    }
+}
+
+/// Registers a callback to be called with the full report during ASan error
+/// reporting.
+///
+/// Callbacks are called in registration order. Panics in callbacks are caught
+/// and ignored. Registering a callback replaces any callback set with
+/// `set_error_report_callback`, and vice versa.
+pub fn on_error_report<F>(callback: F) -> ErrorReportHandle
+where
+    F: Fn(&str) + Send + Sync + 'static,
+{
+    let id = NEXT_ERROR_REPORT_CALLBACK_ID.fetch_add(1, Ordering::Relaxed);
+    ERROR_REPORT_CALLBACKS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push((id, Arc::new(callback)));
+    set_error_report_callback(Some(error_report_callback));
+    ErrorReportHandle { id }
+}
+
+/// Sets a callback to be called right before ASan terminates the process
+/// after reporting an error (e.g., to flush buffers or write crash artifacts).
+///
+/// The runtime has a single death callback, so the callback replaces any
+/// callback previously set (including with `common::set_death_callback`).
+/// Panics in the callback are caught and ignored.
+pub fn set_death_callback<F>(callback: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    common::store_death_callback(Some(Box::new(callback)));
+    unsafe {
+        __asan_set_death_callback(Some(common::death_callback));
+    }
+}
+
+/// Clears the callback set with `set_death_callback`.
+pub fn clear_death_callback() {
+    unsafe {
+        __asan_set_death_callback(None);
+    }
+    common::store_death_callback(None);
+}
+
 /// Prints accumulated statistics to stderr.
 pub fn print_accumulated_stats() {
     unsafe {
@@ -104,7 +722,8 @@ pub fn print_accumulated_stats() {
     }
 }
 
-/// User-provided default option settings.
+/// User-provided default option settings (e.g., defined with
+/// `sanitizers::default_options!`).
 pub fn default_options() -> String {
     unsafe {
         let options_ptr = __asan_default_options();
@@ -115,3 +734,668 @@ pub fn default_options() -> String {
         }
     }
 }
+
+/// An opaque handle to the fake stack of a thread (i.e., the memory where the
+/// stack frames are allocated when `detect_stack_use_after_return` is enabled),
+/// as returned by `current_fake_stack`.
+///
+/// The handle is not `Send` because the fake stack is freed when its owner
+/// thread exits. To inspect the fake stack of another thread (e.g., from a
+/// garbage collector), use `FakeStack::as_ptr` and `FakeStack::from_ptr`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FakeStack {
+    handle: *mut c_void,
+}
+
+/// Boundaries of a fake stack frame, as returned by `FakeStack::contains`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FrameRange {
+    /// Beginning of the fake frame.
+    pub begin: *mut c_void,
+    /// End of the fake frame.
+    pub end: *mut c_void,
+    /// Address of the real stack that corresponds to the fake frame.
+    pub real_stack: *mut c_void,
+}
+
+impl FakeStack {
+    /// Creates a handle from a pointer returned by `FakeStack::as_ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `FakeStack::as_ptr`, and the thread that
+    /// owns the fake stack must be alive while the returned handle is used.
+    pub unsafe fn from_ptr(ptr: *mut c_void) -> Option<Self> {
+        if ptr.is_null() {
+            None
+        } else {
+            Some(FakeStack { handle: ptr })
+        }
+    }
+
+    /// Returns the opaque pointer to the fake stack.
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.handle
+    }
+
+    /// Returns the boundaries of the fake frame that contains the given
+    /// address, or `None` if the address does not belong to a fake frame of
+    /// this fake stack.
+    ///
+    /// # Safety
+    ///
+    /// `addr` must be an address in the application memory.
+    pub unsafe fn contains(&self, addr: *const c_void) -> Option<FrameRange> {
+        let mut begin: *mut c_void = std::ptr::null_mut();
+        let mut end: *mut c_void = std::ptr::null_mut();
+        let real_stack = unsafe {
+            __asan_addr_is_in_fake_stack(self.handle, addr as *mut c_void, &mut begin, &mut end)
+        };
+        if real_stack.is_null() {
+            None
+        } else {
+            Some(FrameRange {
+                begin,
+                end,
+                real_stack,
+            })
+        }
+    }
+
+    /// Returns the boundaries of the fake frame that contains `value`, or
+    /// `None` if `value` does not belong to a fake frame of this fake stack.
+    pub fn contains_val<T: ?Sized>(&self, value: &T) -> Option<FrameRange> {
+        unsafe { self.contains(value as *const T as *const c_void) }
+    }
+}
+
+/// Gets a handle to the fake stack of the current thread, or `None` if the
+/// current thread does not have a fake stack (e.g., if
+/// `detect_stack_use_after_return` is not enabled).
+pub fn current_fake_stack() -> Option<FakeStack> {
+    unsafe { FakeStack::from_ptr(__asan_get_current_fake_stack()) }
+}
+
+/// Cleans up the poisoning of the current thread's stack (i.e., the redzones of
+/// the current frame and its callers) before the frames are left without
+/// returning (e.g., by a `longjmp` or a coroutine switch that ASan does not
+/// intercept), so that the stale poisoning does not cause false positives.
+///
+/// Stacks other than the stack of the current thread (e.g., coroutine stacks)
+/// are not cleaned up; use `unpoison_memory_region` for them instead.
+pub fn handle_no_return() {
+    unsafe {
+        __asan_handle_no_return();
+    }
+}
+
+/// A guard that calls `handle_no_return` when dropped, including when the
+/// scope is left by unwinding.
+///
+/// E.g.:
+///
+/// ```ignore
+/// let guard = NoReturnGuard::new();
+/// ...
+/// drop(guard);
+/// longjmp(env, 1);
+/// ```
+#[derive(Debug)]
+pub struct NoReturnGuard {
+    // The guard cleans up the stack of the thread that drops it.
+    _marker: PhantomData<*const ()>,
+}
+
+impl NoReturnGuard {
+    /// Creates a new guard.
+    pub fn new() -> Self {
+        NoReturnGuard {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Default for NoReturnGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for NoReturnGuard {
+    fn drop(&mut self) {
+        handle_no_return();
+    }
+}
+
+/// Size in bytes of the memory granules in which AddressSanitizer tracks
+/// addressability (i.e., the number of application bytes described by each
+/// shadow byte with the default shadow scale).
+pub const SHADOW_GRANULARITY: usize = 8;
+
+/// A guard that poisons a memory region while it is alive and restores its
+/// addressability when dropped (including during unwinding).
+///
+/// AddressSanitizer tracks addressability in granules of `SHADOW_GRANULARITY`
+/// bytes and can only mark a granule as addressable up to a given offset.
+/// Therefore, if the region ends in the middle of a granule whose remaining
+/// bytes are addressable, the bytes of the region in that granule cannot be
+/// poisoned without also poisoning the bytes that follow it. Use
+/// `PoisonGuard::poisoned_len` to check how much of the region was actually
+/// poisoned.
+pub struct PoisonGuard<'a> {
+    size: usize,
+    addressable: Vec<Range<usize>>,
+    poisoned_len: usize,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> PoisonGuard<'a> {
+    /// Poisons the memory of `value` (e.g., a slice) until the guard is
+    /// dropped.
+    pub fn new<T: ?Sized>(value: &'a T) -> Self {
+        unsafe { Self::from_raw(value as *const T as *const c_void, size_of_val(value)) }
+    }
+
+    /// Poisons a memory region until the guard is dropped.
+    ///
+    /// # Safety
+    ///
+    /// `addr` must point to a memory region of at least `size` bytes that was
+    /// previously allocated by the program and that outlives the guard.
+    pub unsafe fn from_raw(addr: *const c_void, size: usize) -> Self {
+        // Only the bytes that are addressable now are made addressable again
+        // when the guard is dropped, so nested guards and already poisoned
+        // regions (including holes in the middle of the region) are restored
+        // correctly.
+        let addressable = unsafe { Self::addressable_ranges(addr as usize, size) };
+        unsafe { poison_memory_region(addr, size) };
+        let poisoned_len = unsafe { Self::compute_poisoned_len(addr, size) };
+        PoisonGuard {
+            size,
+            addressable,
+            poisoned_len,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the addressable byte ranges of a memory region.
+    unsafe fn addressable_ranges(addr: usize, size: usize) -> Vec<Range<usize>> {
+        let end = addr + size;
+        // Only a prefix of each granule can be addressable, so the next
+        // addressable byte after a poisoned one is at the start of a granule.
+        let next_addressable = |mut pos: usize| {
+            while pos < end && unsafe { is_address_poisoned(pos as *const c_void) } {
+                pos = (pos / SHADOW_GRANULARITY + 1) * SHADOW_GRANULARITY;
+            }
+            pos.min(end)
+        };
+        let mut ranges = Vec::new();
+        let mut pos = next_addressable(addr);
+        while pos < end {
+            let run_end = unsafe { region_is_poisoned(pos as *mut c_void, end - pos) }
+                .map_or(end, |first| first as usize);
+            ranges.push(pos..run_end);
+            pos = next_addressable(run_end);
+        }
+        ranges
+    }
+
+    unsafe fn compute_poisoned_len(addr: *const c_void, size: usize) -> usize {
+        if unsafe { region_is_poisoned(addr as *mut c_void, size) }.is_none() {
+            return 0;
+        }
+        let end = addr as usize + size;
+        if end.is_multiple_of(SHADOW_GRANULARITY)
+            || unsafe { is_address_poisoned((end - 1) as *const c_void) }
+        {
+            return size;
+        }
+        // The last, partially covered granule could not be poisoned.
+        (end - end % SHADOW_GRANULARITY).saturating_sub(addr as usize)
+    }
+
+    /// Returns the size of the region.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// Returns whether the region is empty.
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Returns the size of the prefix of the region that is poisoned (i.e., the
+    /// size of the region unless it ends in a partially covered granule whose
+    /// remaining bytes are addressable).
+    pub fn poisoned_len(&self) -> usize {
+        self.poisoned_len
+    }
+
+    /// Returns whether the entire region is poisoned.
+    pub fn is_fully_poisoned(&self) -> bool {
+        self.poisoned_len == self.size
+    }
+}
+
+impl Drop for PoisonGuard<'_> {
+    fn drop(&mut self) {
+        for range in &self.addressable {
+            unsafe { unpoison_memory_region(range.start as *const c_void, range.len()) };
+        }
+    }
+}
+
+/// AddressSanitizer shadow memory mapping (i.e., the shadow byte describing an
+/// application address `addr` is at `(addr >> scale) + offset`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ShadowMapping {
+    /// Shadow scale (i.e., the base 2 logarithm of the shadow granularity).
+    pub scale: usize,
+    /// Shadow offset.
+    pub offset: usize,
+}
+
+impl ShadowMapping {
+    /// Returns the number of application bytes described by each shadow byte.
+    #[inline(always)]
+    pub fn granularity(&self) -> usize {
+        1 << self.scale
+    }
+
+    /// Returns the address of the shadow byte describing the given application
+    /// address.
+    #[inline(always)]
+    pub fn shadow_address(&self, addr: *const c_void) -> *const u8 {
+        ((addr as usize >> self.scale) + self.offset) as *const u8
+    }
+}
+
+/// Gets the current shadow memory mapping, or `None` if the program is not
+/// built with AddressSanitizer.
+pub fn shadow_mapping() -> Option<ShadowMapping> {
+    let mut scale = 0;
+    let mut offset = 0;
+    unsafe {
+        __asan_get_shadow_mapping(&mut scale, &mut offset);
+    }
+    if scale == 0 {
+        None
+    } else {
+        Some(ShadowMapping { scale, offset })
+    }
+}
+
+/// Reads a shadow byte.
+///
+/// Shadow memory must not be read by instrumented code, because the
+/// instrumentation would check the shadow of the shadow memory (i.e., the
+/// protected shadow gap), so the shadow byte is read with inline assembly where
+/// available.
+///
+/// # Safety
+///
+/// `shadow` must be the address of the shadow byte of an address in the
+/// application memory.
+#[inline(always)]
+unsafe fn read_shadow_byte(shadow: *const u8) -> u8 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        let value: u8;
+        unsafe {
+            std::arch::asm!(
+                "mov {value}, byte ptr [{shadow}]",
+                shadow = in(reg) shadow,
+                value = out(reg_byte) value,
+                options(pure, nostack, readonly, preserves_flags),
+            );
+        }
+        value
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        let value: u32;
+        unsafe {
+            std::arch::asm!(
+                "ldrb {value:w}, [{shadow}]",
+                shadow = in(reg) shadow,
+                value = out(reg) value,
+                options(pure, nostack, readonly, preserves_flags),
+            );
+        }
+        value as u8
+    }
+    #[cfg(target_arch = "riscv64")]
+    {
+        let value: usize;
+        unsafe {
+            std::arch::asm!(
+                "lbu {value}, 0({shadow})",
+                shadow = in(reg) shadow,
+                value = out(reg) value,
+                options(pure, nostack, readonly, preserves_flags),
+            );
+        }
+        value as u8
+    }
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )))]
+    unsafe {
+        shadow.read_volatile()
+    }
+}
+
+/// Reads eight consecutive shadow bytes at once.
+///
+/// # Safety
+///
+/// `shadow` must be the address of the shadow byte of an address in the
+/// application memory, aligned to eight bytes, and followed by (at least) seven
+/// shadow bytes of addresses in the application memory.
+#[inline(always)]
+unsafe fn read_shadow_word(shadow: *const u8) -> u64 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        let value: u64;
+        unsafe {
+            std::arch::asm!(
+                "mov {value}, qword ptr [{shadow}]",
+                shadow = in(reg) shadow,
+                value = out(reg) value,
+                options(pure, nostack, readonly, preserves_flags),
+            );
+        }
+        value
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        let value: u64;
+        unsafe {
+            std::arch::asm!(
+                "ldr {value}, [{shadow}]",
+                shadow = in(reg) shadow,
+                value = out(reg) value,
+                options(pure, nostack, readonly, preserves_flags),
+            );
+        }
+        value
+    }
+    #[cfg(target_arch = "riscv64")]
+    {
+        let value: u64;
+        unsafe {
+            std::arch::asm!(
+                "ld {value}, 0({shadow})",
+                shadow = in(reg) shadow,
+                value = out(reg) value,
+                options(pure, nostack, readonly, preserves_flags),
+            );
+        }
+        value
+    }
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )))]
+    unsafe {
+        (shadow as *const u64).read_volatile()
+    }
+}
+
+/// Checks whether 64 consecutive shadow bytes are all zero (i.e., the 64
+/// granules they describe are addressable) with as few loads as possible.
+///
+/// # Safety
+///
+/// `shadow` must be the address of the shadow byte of an address in the
+/// application memory, aligned to eight bytes, and followed by (at least) 63
+/// shadow bytes of addresses in the application memory.
+#[inline(always)]
+unsafe fn shadow_block_is_zero(shadow: *const u8) -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SSE2 is part of the x86_64 baseline.
+        let mask: u32;
+        unsafe {
+            std::arch::asm!(
+                "movdqu {acc}, xmmword ptr [{shadow}]",
+                "movdqu {tmp}, xmmword ptr [{shadow} + 16]",
+                "por {acc}, {tmp}",
+                "movdqu {tmp}, xmmword ptr [{shadow} + 32]",
+                "por {acc}, {tmp}",
+                "movdqu {tmp}, xmmword ptr [{shadow} + 48]",
+                "por {acc}, {tmp}",
+                "pxor {tmp}, {tmp}",
+                "pcmpeqb {acc}, {tmp}",
+                "pmovmskb {mask:e}, {acc}",
+                shadow = in(reg) shadow,
+                acc = out(xmm_reg) _,
+                tmp = out(xmm_reg) _,
+                mask = out(reg) mask,
+                options(pure, nostack, readonly, preserves_flags),
+            );
+        }
+        mask == 0xffff
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let mut value = 0;
+        let mut i = 0;
+        while i < 64 {
+            value |= unsafe { read_shadow_word(shadow.wrapping_add(i)) };
+            i += 8;
+        }
+        value == 0
+    }
+}
+
+/// Value of a shadow byte.
+///
+/// For more information about the shadow byte values, see the legend of the
+/// shadow bytes in the AddressSanitizer reports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShadowByte {
+    /// All the bytes of the granule are addressable (`00`).
+    Addressable,
+    /// Only the given number of leading bytes of the granule are addressable
+    /// (`01` to `07` with the default shadow scale).
+    PartiallyAddressable(u8),
+    /// Heap left (or right) redzone (`fa`).
+    HeapLeftRedzone,
+    /// Freed heap region (`fd`).
+    FreedHeapRegion,
+    /// Stack left redzone (`f1`).
+    StackLeftRedzone,
+    /// Stack mid redzone (`f2`).
+    StackMidRedzone,
+    /// Stack right redzone (`f3`).
+    StackRightRedzone,
+    /// Stack after return (`f5`).
+    StackAfterReturn,
+    /// Stack use after scope (`f8`).
+    StackUseAfterScope,
+    /// Global redzone (`f9`).
+    GlobalRedzone,
+    /// Global initialization order (`f6`).
+    GlobalInitOrder,
+    /// Poisoned by user (`f7`).
+    UserPoisoned,
+    /// Container overflow (`fc`).
+    ContainerOverflow,
+    /// Array cookie (`ac`).
+    ArrayCookie,
+    /// Intra-object redzone (`bb`).
+    IntraObjectRedzone,
+    /// AddressSanitizer internal (`fe`).
+    AsanInternal,
+    /// Left alloca redzone (`ca`).
+    LeftAllocaRedzone,
+    /// Right alloca redzone (`cb`).
+    RightAllocaRedzone,
+    /// Any other value.
+    Unknown(u8),
+}
+
+impl ShadowByte {
+    /// Converts a raw shadow byte.
+    pub fn from_byte(byte: u8) -> Self {
+        match byte {
+            0x00 => ShadowByte::Addressable,
+            0x01..=0x7f => ShadowByte::PartiallyAddressable(byte),
+            0xfa => ShadowByte::HeapLeftRedzone,
+            0xfd => ShadowByte::FreedHeapRegion,
+            0xf1 => ShadowByte::StackLeftRedzone,
+            0xf2 => ShadowByte::StackMidRedzone,
+            0xf3 => ShadowByte::StackRightRedzone,
+            0xf5 => ShadowByte::StackAfterReturn,
+            0xf8 => ShadowByte::StackUseAfterScope,
+            0xf9 => ShadowByte::GlobalRedzone,
+            0xf6 => ShadowByte::GlobalInitOrder,
+            0xf7 => ShadowByte::UserPoisoned,
+            0xfc => ShadowByte::ContainerOverflow,
+            0xac => ShadowByte::ArrayCookie,
+            0xbb => ShadowByte::IntraObjectRedzone,
+            0xfe => ShadowByte::AsanInternal,
+            0xca => ShadowByte::LeftAllocaRedzone,
+            0xcb => ShadowByte::RightAllocaRedzone,
+            _ => ShadowByte::Unknown(byte),
+        }
+    }
+
+    /// Returns the raw shadow byte.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            ShadowByte::Addressable => 0x00,
+            ShadowByte::PartiallyAddressable(byte) => byte,
+            ShadowByte::HeapLeftRedzone => 0xfa,
+            ShadowByte::FreedHeapRegion => 0xfd,
+            ShadowByte::StackLeftRedzone => 0xf1,
+            ShadowByte::StackMidRedzone => 0xf2,
+            ShadowByte::StackRightRedzone => 0xf3,
+            ShadowByte::StackAfterReturn => 0xf5,
+            ShadowByte::StackUseAfterScope => 0xf8,
+            ShadowByte::GlobalRedzone => 0xf9,
+            ShadowByte::GlobalInitOrder => 0xf6,
+            ShadowByte::UserPoisoned => 0xf7,
+            ShadowByte::ContainerOverflow => 0xfc,
+            ShadowByte::ArrayCookie => 0xac,
+            ShadowByte::IntraObjectRedzone => 0xbb,
+            ShadowByte::AsanInternal => 0xfe,
+            ShadowByte::LeftAllocaRedzone => 0xca,
+            ShadowByte::RightAllocaRedzone => 0xcb,
+            ShadowByte::Unknown(byte) => byte,
+        }
+    }
+
+    /// Returns the number of leading bytes of the granule that are addressable,
+    /// given the shadow granularity.
+    pub fn addressable_len(self, granularity: usize) -> usize {
+        match self {
+            ShadowByte::Addressable => granularity,
+            ShadowByte::PartiallyAddressable(len) => len as usize,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for ShadowByte {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}", self.to_byte())
+    }
+}
+
+/// Shadow bytes describing a memory region.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShadowBytes {
+    addr: *const c_void,
+    shadow_addr: *const u8,
+    bytes: Vec<ShadowByte>,
+}
+
+impl ShadowBytes {
+    /// Returns the address of the first granule described by the shadow bytes
+    /// (i.e., the address of the region rounded down to the shadow
+    /// granularity).
+    pub fn addr(&self) -> *const c_void {
+        self.addr
+    }
+
+    /// Returns the address of the first shadow byte.
+    pub fn shadow_addr(&self) -> *const u8 {
+        self.shadow_addr
+    }
+
+    /// Returns the shadow bytes.
+    pub fn as_slice(&self) -> &[ShadowByte] {
+        &self.bytes
+    }
+
+    /// Converts the shadow bytes into a vector.
+    pub fn into_vec(self) -> Vec<ShadowByte> {
+        self.bytes
+    }
+
+    /// Returns the number of shadow bytes.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns whether there are no shadow bytes.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+impl fmt::Display for ShadowBytes {
+    /// Renders the shadow bytes as a table of 16 shadow bytes per row, each row
+    /// prefixed by the address of its first shadow byte, like the shadow bytes
+    /// in the AddressSanitizer reports.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (row, bytes) in self.bytes.chunks(16).enumerate() {
+            write!(f, "{:#014x}:", self.shadow_addr as usize + row * 16)?;
+            for byte in bytes {
+                write!(f, " {}", byte)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the shadow bytes describing a memory region (i.e., of the granules
+/// that overlap the region), or no shadow bytes if the program is not built
+/// with AddressSanitizer.
+///
+/// # Safety
+///
+/// `addr` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn shadow_bytes(addr: *const c_void, size: usize) -> ShadowBytes {
+    let Some(mapping) = shadow_mapping() else {
+        return ShadowBytes {
+            addr,
+            shadow_addr: std::ptr::null(),
+            bytes: Vec::new(),
+        };
+    };
+    let granularity = mapping.granularity();
+    let start = addr as usize & !(granularity - 1);
+    let end = (addr as usize + size).div_ceil(granularity) * granularity;
+    let shadow_addr = mapping.shadow_address(start as *const c_void);
+    let bytes = (0..(end - start) / granularity)
+        .map(|i| ShadowByte::from_byte(unsafe { read_shadow_byte(shadow_addr.add(i)) }))
+        .collect();
+    ShadowBytes {
+        addr: start as *const c_void,
+        shadow_addr,
+        bytes,
+    }
+}
+
+/// Returns the shadow bytes describing the memory of `value` (e.g., a slice).
+pub fn shadow_bytes_val<T: ?Sized>(value: &T) -> ShadowBytes {
+    unsafe { shadow_bytes(value as *const T as *const c_void, size_of_val(value)) }
+}
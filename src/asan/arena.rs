@@ -0,0 +1,162 @@
+use super::{poison_memory_region, unpoison_memory_region, SHADOW_GRANULARITY};
+
+use std::alloc::{self, Layout};
+use std::cell::Cell;
+use std::fmt;
+use std::os::raw::c_void;
+use std::ptr::{self, NonNull};
+
+/// Default size, in bytes, of the redzone between allocations.
+const DEFAULT_REDZONE_SIZE: usize = 16;
+
+/// A bump arena that allocates objects from a single chunk of memory and keeps
+/// all the memory of the chunk that is not handed out (i.e., the padding and the
+/// redzones between the allocations, and the free memory at the end of the
+/// chunk) poisoned.
+///
+/// Each allocation starts at a new shadow granule, after a redzone of
+/// configurable size, so that overflows into the next object are reported by
+/// AddressSanitizer. The entire chunk is poisoned again when the arena is reset,
+/// so that uses after reset (e.g., through raw pointers) are reported as well.
+///
+/// The values allocated in the arena are never dropped.
+pub struct AsanArena {
+    chunk: NonNull<u8>,
+    capacity: usize,
+    offset: Cell<usize>,
+    redzone: usize,
+}
+
+// SAFETY: The chunk is owned by the arena.
+unsafe impl Send for AsanArena {}
+
+impl AsanArena {
+    /// Creates a new arena with a chunk of the given size, in bytes.
+    pub fn new(capacity: usize) -> Self {
+        let chunk = if capacity == 0 {
+            NonNull::dangling()
+        } else {
+            let layout = Self::chunk_layout(capacity);
+            let chunk = unsafe { alloc::alloc(layout) };
+            NonNull::new(chunk).unwrap_or_else(|| alloc::handle_alloc_error(layout))
+        };
+        let arena = AsanArena {
+            chunk,
+            capacity,
+            offset: Cell::new(0),
+            redzone: DEFAULT_REDZONE_SIZE,
+        };
+        arena.poison_chunk();
+        arena
+    }
+
+    /// Sets the minimum size, in bytes, of the redzone between allocations
+    /// (16 bytes by default).
+    pub fn redzone(mut self, size: usize) -> Self {
+        self.redzone = size;
+        self
+    }
+
+    /// Returns the size, in bytes, of the chunk.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns a raw pointer to the chunk.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.chunk.as_ptr()
+    }
+
+    /// Returns the number of bytes of the chunk used since the arena was created
+    /// or reset, including padding and redzones.
+    pub fn allocated_bytes(&self) -> usize {
+        self.offset.get()
+    }
+
+    /// Allocates memory for the given layout and unpoisons exactly the
+    /// allocated memory, or returns `None` if the chunk is exhausted.
+    pub fn alloc_layout(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let offset = self.offset.get();
+        let redzone = if offset == 0 { 0 } else { self.redzone };
+        let align = layout.align().max(SHADOW_GRANULARITY);
+        let start = (self.chunk.as_ptr() as usize)
+            .checked_add(offset)?
+            .checked_add(redzone)?
+            .checked_next_multiple_of(align)?
+            - self.chunk.as_ptr() as usize;
+        let end = start.checked_add(layout.size())?;
+        if end > self.capacity {
+            return None;
+        }
+        self.offset.set(end);
+        let ptr = self.chunk.as_ptr().wrapping_add(start);
+        unsafe { unpoison_memory_region(ptr as *const c_void, layout.size()) };
+        NonNull::new(ptr)
+    }
+
+    /// Moves a value into the arena and returns a reference to it, or returns
+    /// the value back if the chunk is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T>(&self, value: T) -> Result<&mut T, T> {
+        match self.alloc_layout(Layout::new::<T>()) {
+            Some(ptr) => {
+                let ptr = ptr.as_ptr() as *mut T;
+                unsafe {
+                    ptr::write(ptr, value);
+                    Ok(&mut *ptr)
+                }
+            }
+            None => Err(value),
+        }
+    }
+
+    /// Copies a slice into the arena and returns a reference to the copy, or
+    /// `None` if the chunk is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_copy<T: Copy>(&self, values: &[T]) -> Option<&mut [T]> {
+        let ptr = self.alloc_layout(Layout::for_value(values))?.as_ptr() as *mut T;
+        unsafe {
+            ptr::copy_nonoverlapping(values.as_ptr(), ptr, values.len());
+            Some(std::slice::from_raw_parts_mut(ptr, values.len()))
+        }
+    }
+
+    /// Frees all the allocations at once and poisons the entire chunk.
+    pub fn reset(&mut self) {
+        self.offset.set(0);
+        self.poison_chunk();
+    }
+
+    fn chunk_layout(capacity: usize) -> Layout {
+        Layout::from_size_align(capacity, SHADOW_GRANULARITY).expect("arena capacity overflow")
+    }
+
+    fn poison_chunk(&self) {
+        if self.capacity == 0 {
+            return;
+        }
+        unsafe { poison_memory_region(self.chunk.as_ptr() as *const c_void, self.capacity) };
+    }
+}
+
+impl fmt::Debug for AsanArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsanArena")
+            .field("capacity", &self.capacity)
+            .field("allocated_bytes", &self.offset.get())
+            .field("redzone", &self.redzone)
+            .finish()
+    }
+}
+
+impl Drop for AsanArena {
+    fn drop(&mut self) {
+        if self.capacity == 0 {
+            return;
+        }
+        unsafe {
+            unpoison_memory_region(self.chunk.as_ptr() as *const c_void, self.capacity);
+            alloc::dealloc(self.chunk.as_ptr(), Self::chunk_layout(self.capacity));
+        }
+    }
+}
@@ -0,0 +1,636 @@
+use crate::ffi::asan::*;
+
+use std::fmt;
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::c_void;
+
+/// Annotates the current state of a contiguous container (i.e., a container
+/// that owns the memory region `[beg, end)`, stores its elements in
+/// `[beg, mid)`, and reserves `[mid, end)` for future elements), so that
+/// accesses to `[new_mid, end)` are reported as container overflows.
+///
+/// In the initial state `mid == end`, so that should be the final state when
+/// the container is destroyed or reallocates its storage.
+///
+/// # Safety
+///
+/// `[beg, end)` must be a memory region allocated by the program and owned by
+/// the container, `old_mid` must be the middle of the region as last annotated
+/// (or `end`), and `beg <= new_mid <= end`.
+pub unsafe fn annotate_contiguous_container(
+    beg: *const c_void,
+    end: *const c_void,
+    old_mid: *const c_void,
+    new_mid: *const c_void,
+) {
+    unsafe {
+        __sanitizer_annotate_contiguous_container(beg, end, old_mid, new_mid);
+    }
+}
+
+/// Checks if the contiguous container `[beg, end)` is properly annotated (i.e.,
+/// `[beg, mid)` is addressable and `[mid, end)` is unaddressable).
+///
+/// Only the memory around `beg`, `mid`, and `end` is checked.
+///
+/// # Safety
+///
+/// `[beg, end)` must be a memory region allocated by the program and
+/// `beg <= mid <= end`.
+pub unsafe fn verify_contiguous_container(
+    beg: *const c_void,
+    mid: *const c_void,
+    end: *const c_void,
+) -> bool {
+    unsafe { __sanitizer_verify_contiguous_container(beg, mid, end) != 0 }
+}
+
+/// Returns the address of the first improperly annotated byte of the contiguous
+/// container `[beg, end)`, or `None` if the container is properly annotated.
+///
+/// # Safety
+///
+/// `[beg, end)` must be a memory region allocated by the program and
+/// `beg <= mid <= end`.
+pub unsafe fn contiguous_container_find_bad_address(
+    beg: *const c_void,
+    mid: *const c_void,
+    end: *const c_void,
+) -> Option<*const c_void> {
+    let addr = unsafe { __sanitizer_contiguous_container_find_bad_address(beg, mid, end) };
+    if addr.is_null() {
+        None
+    } else {
+        Some(addr)
+    }
+}
+
+/// Annotates the current state of a double-ended contiguous container (i.e., a
+/// container that owns the memory region `[storage_beg, storage_end)`, stores
+/// its elements in `[container_beg, container_end)`, and reserves
+/// `[storage_beg, container_beg)` and `[container_end, storage_end)` for future
+/// elements), so that accesses to the reserved memory are reported as container
+/// overflows.
+///
+/// In the initial state the whole storage is addressable, so that should be the
+/// final state when the container is destroyed or reallocates its storage.
+///
+/// # Safety
+///
+/// `[storage_beg, storage_end)` must be a memory region allocated by the program
+/// and owned by the container, `[old_container_beg, old_container_end)` must be
+/// the used region as last annotated (or an empty region when unpoisoning the
+/// storage of an empty container), and `storage_beg <= new_container_beg <=
+/// new_container_end <= storage_end`.
+pub unsafe fn annotate_double_ended_contiguous_container(
+    storage_beg: *const c_void,
+    storage_end: *const c_void,
+    old_container_beg: *const c_void,
+    old_container_end: *const c_void,
+    new_container_beg: *const c_void,
+    new_container_end: *const c_void,
+) {
+    unsafe {
+        __sanitizer_annotate_double_ended_contiguous_container(
+            storage_beg,
+            storage_end,
+            old_container_beg,
+            old_container_end,
+            new_container_beg,
+            new_container_end,
+        );
+    }
+}
+
+/// Checks if the double-ended contiguous container `[storage_beg, storage_end)`
+/// is properly annotated (i.e., only `[container_beg, container_end)` is
+/// addressable).
+///
+/// Only the memory around the beginnings and ends of the storage and the
+/// container is checked.
+///
+/// # Safety
+///
+/// `[storage_beg, storage_end)` must be a memory region allocated by the program
+/// and `storage_beg <= container_beg <= container_end <= storage_end`.
+pub unsafe fn verify_double_ended_contiguous_container(
+    storage_beg: *const c_void,
+    container_beg: *const c_void,
+    container_end: *const c_void,
+    storage_end: *const c_void,
+) -> bool {
+    unsafe {
+        __sanitizer_verify_double_ended_contiguous_container(
+            storage_beg,
+            container_beg,
+            container_end,
+            storage_end,
+        ) != 0
+    }
+}
+
+/// Returns the address of the first improperly annotated byte of the
+/// double-ended contiguous container `[storage_beg, storage_end)`, or `None` if
+/// the container is properly annotated.
+///
+/// # Safety
+///
+/// `[storage_beg, storage_end)` must be a memory region allocated by the program
+/// and `storage_beg <= container_beg <= container_end <= storage_end`.
+pub unsafe fn double_ended_contiguous_container_find_bad_address(
+    storage_beg: *const c_void,
+    container_beg: *const c_void,
+    container_end: *const c_void,
+    storage_end: *const c_void,
+) -> Option<*const c_void> {
+    let addr = unsafe {
+        __sanitizer_double_ended_contiguous_container_find_bad_address(
+            storage_beg,
+            container_beg,
+            container_end,
+            storage_end,
+        )
+    };
+    if addr.is_null() {
+        None
+    } else {
+        Some(addr)
+    }
+}
+
+/// Container annotations for the storage of a ring buffer (e.g., a
+/// `VecDeque`-style queue) that stores its elements in a window of `len`
+/// elements starting at `head` and wrapping around the end of the storage.
+///
+/// The memory outside the window (i.e., both the spare region before the head
+/// and the spare region after the tail, or the single spare region between the
+/// tail and the head when the window wraps) is kept unaddressable, so that
+/// accesses to it are reported as container overflows. Spare memory that
+/// shares a shadow granule with elements of the window (see
+/// [`super::ShadowMapping::granularity`]) is left addressable. The annotations are
+/// removed when the value is dropped.
+///
+/// This type does not own the storage or the elements; the ring buffer must
+/// call [`RingAnnotations::set_window`] whenever it moves its head or tail.
+pub struct RingAnnotations<T> {
+    storage: *const T,
+    capacity: usize,
+    head: usize,
+    len: usize,
+}
+
+// SAFETY: The storage is only used for computing the addresses of the
+// annotations, which are updated through `&mut self`.
+unsafe impl<T: Send> Send for RingAnnotations<T> {}
+unsafe impl<T: Sync> Sync for RingAnnotations<T> {}
+
+impl<T> RingAnnotations<T> {
+    /// Annotates the storage of `capacity` elements of an empty ring buffer
+    /// (i.e., makes the whole storage unaddressable).
+    ///
+    /// # Safety
+    ///
+    /// `storage` must point to a memory region of `capacity` elements allocated
+    /// by the program and owned by the ring buffer that is not otherwise
+    /// annotated, and the region must outlive the returned value.
+    pub unsafe fn new(storage: *const T, capacity: usize) -> Self {
+        let ring = RingAnnotations {
+            storage,
+            capacity,
+            head: 0,
+            len: 0,
+        };
+        if let Some((storage_beg, storage_end)) = ring.storage() {
+            unsafe {
+                annotate_double_ended_contiguous_container(
+                    storage_beg,
+                    storage_end,
+                    storage_beg,
+                    storage_end,
+                    storage_beg,
+                    storage_beg,
+                );
+            }
+        }
+        ring
+    }
+
+    /// Returns the index of the first element of the window.
+    pub fn head(&self) -> usize {
+        self.head
+    }
+
+    /// Returns the number of elements in the window.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the window is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of elements of the storage.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Moves the window to the `len` elements starting at `head`, wrapping
+    /// around the end of the storage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len > capacity` or, unless the storage is empty,
+    /// `head >= capacity`.
+    pub fn set_window(&mut self, head: usize, len: usize) {
+        assert!(len <= self.capacity, "window length out of bounds");
+        assert!(
+            head < self.capacity || self.capacity == 0,
+            "window head out of bounds"
+        );
+        if (head, len) == (self.head, self.len) {
+            return;
+        }
+        self.unannotate();
+        self.head = head;
+        self.len = len;
+        self.annotate();
+    }
+
+    /// Checks if the storage is properly annotated for the current window.
+    pub fn verify(&self) -> bool {
+        let Some((storage_beg, storage_end)) = self.storage() else {
+            return true;
+        };
+        let head = self.element(self.head);
+        if !self.is_wrapped() {
+            let tail = self.element(self.head + self.len);
+            unsafe {
+                verify_double_ended_contiguous_container(storage_beg, head, tail, storage_end)
+            }
+        } else {
+            let tail = self.element(self.tail());
+            let size = storage_end as usize - head as usize;
+            unsafe {
+                verify_contiguous_container(storage_beg, tail, head)
+                    && __asan_region_is_poisoned(head as *mut c_void, size).is_null()
+            }
+        }
+    }
+
+    /// Returns the index one past the last element of a wrapped window.
+    fn tail(&self) -> usize {
+        self.head + self.len - self.capacity
+    }
+
+    /// Returns whether the window wraps around the end of the storage.
+    fn is_wrapped(&self) -> bool {
+        self.head + self.len > self.capacity
+    }
+
+    /// Returns the address of the element at the given index.
+    fn element(&self, index: usize) -> *const c_void {
+        self.storage.wrapping_add(index) as *const c_void
+    }
+
+    /// Returns the beginning and end of the storage, or `None` if it is empty.
+    fn storage(&self) -> Option<(*const c_void, *const c_void)> {
+        if self.capacity == 0 || size_of::<T>() == 0 {
+            return None;
+        }
+        Some((self.element(0), self.element(self.capacity)))
+    }
+
+    /// Makes the storage outside the window unaddressable, assuming the whole
+    /// storage is addressable.
+    fn annotate(&self) {
+        let Some((storage_beg, storage_end)) = self.storage() else {
+            return;
+        };
+        let head = self.element(self.head);
+        unsafe {
+            if !self.is_wrapped() {
+                let tail = self.element(self.head + self.len);
+                annotate_double_ended_contiguous_container(
+                    storage_beg,
+                    storage_end,
+                    storage_beg,
+                    storage_end,
+                    head,
+                    tail,
+                );
+            } else {
+                // The window is split into `[0, tail)` and `[head, capacity)`,
+                // so only `[tail, head)` is spare, which is the spare capacity
+                // of the contiguous container `[0, head)`.
+                let tail = self.element(self.tail());
+                annotate_contiguous_container(storage_beg, head, head, tail);
+            }
+        }
+    }
+
+    /// Makes the whole storage addressable.
+    fn unannotate(&self) {
+        let Some((storage_beg, storage_end)) = self.storage() else {
+            return;
+        };
+        let head = self.element(self.head);
+        unsafe {
+            if !self.is_wrapped() {
+                let tail = self.element(self.head + self.len);
+                annotate_double_ended_contiguous_container(
+                    storage_beg,
+                    storage_end,
+                    head,
+                    tail,
+                    storage_beg,
+                    storage_end,
+                );
+            } else {
+                let tail = self.element(self.tail());
+                annotate_contiguous_container(storage_beg, head, tail, head);
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for RingAnnotations<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RingAnnotations")
+            .field("storage", &self.storage)
+            .field("capacity", &self.capacity)
+            .field("head", &self.head)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<T> Drop for RingAnnotations<T> {
+    fn drop(&mut self) {
+        self.unannotate();
+    }
+}
+
+/// A `Vec<T>` whose spare capacity (i.e., the memory between its length and its
+/// capacity) is annotated as a contiguous container, so that accesses to it
+/// (e.g., through raw pointers in unsafe code) are reported as container
+/// overflows.
+///
+/// The annotations are updated by the methods that change the length or the
+/// capacity of the vector, and removed when the vector is dropped or converted
+/// back into a `Vec<T>`.
+pub struct AsanVec<T> {
+    vec: Vec<T>,
+}
+
+impl<T> AsanVec<T> {
+    /// Creates a new, empty vector.
+    pub const fn new() -> Self {
+        AsanVec { vec: Vec::new() }
+    }
+
+    /// Creates a new, empty vector with at least the given capacity.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from(Vec::with_capacity(capacity))
+    }
+
+    /// Returns the number of elements in the vector.
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    /// Returns whether the vector contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    /// Returns the number of elements the vector can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.vec.capacity()
+    }
+
+    /// Returns the elements of the vector.
+    pub fn as_slice(&self) -> &[T] {
+        &self.vec
+    }
+
+    /// Returns the elements of the vector.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.vec
+    }
+
+    /// Returns a raw pointer to the buffer of the vector.
+    pub fn as_ptr(&self) -> *const T {
+        self.vec.as_ptr()
+    }
+
+    /// Returns a raw pointer to the buffer of the vector.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.vec.as_mut_ptr()
+    }
+
+    /// Appends an element to the back of the vector.
+    pub fn push(&mut self, value: T) {
+        if self.vec.len() == self.vec.capacity() {
+            self.realloc(|vec| vec.push(value));
+        } else {
+            self.annotate(self.vec.len(), self.vec.len() + 1);
+            self.vec.push(value);
+        }
+    }
+
+    /// Removes the last element from the vector and returns it, or `None` if
+    /// the vector is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        let value = self.vec.pop()?;
+        self.annotate(self.vec.len() + 1, self.vec.len());
+        Some(value)
+    }
+
+    /// Inserts an element at the given index, shifting all the elements after
+    /// it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(index <= self.vec.len(), "insertion index out of bounds");
+        if self.vec.len() == self.vec.capacity() {
+            self.realloc(|vec| vec.insert(index, value));
+        } else {
+            self.annotate(self.vec.len(), self.vec.len() + 1);
+            self.vec.insert(index, value);
+        }
+    }
+
+    /// Removes and returns the element at the given index, shifting all the
+    /// elements after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn remove(&mut self, index: usize) -> T {
+        let value = self.vec.remove(index);
+        self.annotate(self.vec.len() + 1, self.vec.len());
+        value
+    }
+
+    /// Shortens the vector to the given length, dropping the remaining elements.
+    pub fn truncate(&mut self, len: usize) {
+        let old_len = self.vec.len();
+        self.vec.truncate(len);
+        if self.vec.len() != old_len {
+            self.annotate(old_len, self.vec.len());
+        }
+    }
+
+    /// Removes all the elements of the vector.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    pub fn reserve(&mut self, additional: usize) {
+        if self.vec.capacity() - self.vec.len() < additional {
+            self.realloc(|vec| vec.reserve(additional));
+        }
+    }
+
+    /// Reserves capacity for exactly `additional` more elements.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        if self.vec.capacity() - self.vec.len() < additional {
+            self.realloc(|vec| vec.reserve_exact(additional));
+        }
+    }
+
+    /// Shrinks the capacity of the vector as much as possible.
+    pub fn shrink_to_fit(&mut self) {
+        if self.vec.capacity() != self.vec.len() {
+            self.realloc(|vec| vec.shrink_to_fit());
+        }
+    }
+
+    /// Checks if the spare capacity of the vector is properly annotated.
+    pub fn verify(&self) -> bool {
+        match self.bounds(self.vec.len()) {
+            Some((beg, end, mid)) => unsafe { verify_contiguous_container(beg, mid, end) },
+            None => true,
+        }
+    }
+
+    /// Converts the vector into a `Vec<T>`, removing the annotations.
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.unannotate();
+        std::mem::take(&mut self.vec)
+    }
+
+    /// Returns the beginning, end, and the middle for the given length of the
+    /// buffer, or `None` if the buffer is not allocated.
+    fn bounds(&self, len: usize) -> Option<(*const c_void, *const c_void, *const c_void)> {
+        if self.vec.capacity() == 0 || size_of::<T>() == 0 {
+            return None;
+        }
+        let beg = self.vec.as_ptr();
+        unsafe {
+            Some((
+                beg as *const c_void,
+                beg.add(self.vec.capacity()) as *const c_void,
+                beg.add(len) as *const c_void,
+            ))
+        }
+    }
+
+    /// Moves the middle of the container from `old_len` to `new_len`.
+    fn annotate(&self, old_len: usize, new_len: usize) {
+        if let (Some((beg, end, old_mid)), Some((_, _, new_mid))) =
+            (self.bounds(old_len), self.bounds(new_len))
+        {
+            unsafe { annotate_contiguous_container(beg, end, old_mid, new_mid) };
+        }
+    }
+
+    /// Annotates the spare capacity of a newly (re)allocated buffer.
+    fn annotate_buffer(&self) {
+        if let Some((beg, end, mid)) = self.bounds(self.vec.len()) {
+            unsafe { annotate_contiguous_container(beg, end, end, mid) };
+        }
+    }
+
+    /// Removes the annotations (i.e., restores the initial state of the
+    /// container, in which the middle is the end).
+    fn unannotate(&self) {
+        if let Some((beg, end, mid)) = self.bounds(self.vec.len()) {
+            unsafe { annotate_contiguous_container(beg, end, mid, end) };
+        }
+    }
+
+    /// Performs an operation that may reallocate the buffer, removing the
+    /// annotations before and annotating the new buffer after.
+    fn realloc<R>(&mut self, f: impl FnOnce(&mut Vec<T>) -> R) -> R {
+        self.unannotate();
+        let result = f(&mut self.vec);
+        self.annotate_buffer();
+        result
+    }
+}
+
+impl<T> Default for AsanVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<Vec<T>> for AsanVec<T> {
+    fn from(vec: Vec<T>) -> Self {
+        let vec = AsanVec { vec };
+        vec.annotate_buffer();
+        vec
+    }
+}
+
+impl<T: Clone> Clone for AsanVec<T> {
+    fn clone(&self) -> Self {
+        Self::from(self.vec.clone())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for AsanVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.vec, f)
+    }
+}
+
+impl<T> Deref for AsanVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.vec
+    }
+}
+
+impl<T> DerefMut for AsanVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.vec
+    }
+}
+
+impl<T> Extend<T> for AsanVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for AsanVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(Vec::from_iter(iter))
+    }
+}
+
+impl<T> Drop for AsanVec<T> {
+    fn drop(&mut self) {
+        self.unannotate();
+    }
+}
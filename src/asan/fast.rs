@@ -0,0 +1,138 @@
+use super::{
+    read_shadow_byte, read_shadow_word, shadow_block_is_zero, shadow_mapping, ShadowMapping,
+};
+
+use std::mem::size_of_val;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Shadow mapping the compiler uses for the target (i.e., the scale and offset
+/// hardcoded in the instrumented code), which lets the checks compile to a
+/// shift, an add, and a load.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+const STATIC_MAPPING: Option<ShadowMapping> = Some(ShadowMapping {
+    scale: 3,
+    offset: 0x7fff8000,
+});
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+const STATIC_MAPPING: Option<ShadowMapping> = Some(ShadowMapping {
+    scale: 3,
+    offset: 1 << 36,
+});
+#[cfg(all(target_os = "linux", target_arch = "riscv64"))]
+const STATIC_MAPPING: Option<ShadowMapping> = Some(ShadowMapping {
+    scale: 3,
+    offset: 0xd55550000,
+});
+#[cfg(not(all(
+    target_os = "linux",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+)))]
+const STATIC_MAPPING: Option<ShadowMapping> = None;
+
+// The shadow scale is never zero when the program is built with
+// AddressSanitizer, so zero marks the mapping as not yet queried.
+static SHADOW_SCALE: AtomicUsize = AtomicUsize::new(0);
+static SHADOW_OFFSET: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the shadow memory mapping, querying it from the runtime on first
+/// use on targets without a static mapping.
+#[inline(always)]
+fn mapping() -> Option<ShadowMapping> {
+    if !cfg!(sanitize_address) {
+        return None;
+    }
+    if STATIC_MAPPING.is_some() {
+        return STATIC_MAPPING;
+    }
+    let scale = SHADOW_SCALE.load(Ordering::Acquire);
+    if scale != 0 {
+        return Some(ShadowMapping {
+            scale,
+            offset: SHADOW_OFFSET.load(Ordering::Relaxed),
+        });
+    }
+    init_mapping()
+}
+
+#[cold]
+#[inline(never)]
+fn init_mapping() -> Option<ShadowMapping> {
+    let mapping = shadow_mapping()?;
+    SHADOW_OFFSET.store(mapping.offset, Ordering::Relaxed);
+    SHADOW_SCALE.store(mapping.scale, Ordering::Release);
+    Some(mapping)
+}
+
+/// Checks if an address is poisoned.
+///
+/// Returns `true` if the address is poisoned, `false` otherwise (including
+/// when the program is not built with AddressSanitizer).
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+#[inline(always)]
+pub unsafe fn is_poisoned(addr: *const c_void) -> bool {
+    let Some(mapping) = mapping() else {
+        return false;
+    };
+    // A shadow byte is either zero (the granule is addressable), the number of
+    // leading addressable bytes of the granule, or negative (the granule is
+    // unaddressable).
+    let shadow = unsafe { read_shadow_byte(mapping.shadow_address(addr)) } as i8;
+    shadow != 0 && (addr as usize & (mapping.granularity() - 1)) as i8 >= shadow
+}
+
+/// Checks if the memory of `value` (e.g., a slice) is poisoned.
+///
+/// Returns the offset of the first poisoned byte, or `None` if the memory is
+/// not poisoned (including when the program is not built with
+/// AddressSanitizer).
+#[inline]
+pub fn check_range<T: ?Sized>(value: &T) -> Option<usize> {
+    let mapping = mapping()?;
+    let granularity = mapping.granularity();
+    let beg = value as *const T as *const c_void as usize;
+    let end = beg + size_of_val(value);
+    let mut addr = beg;
+    while addr < end {
+        // Check whole granules a shadow word (i.e., eight shadow bytes) at a
+        // time where possible, 64 shadow bytes at a time for large ranges.
+        let mut shadow = mapping.shadow_address(addr as *const c_void);
+        if addr.is_multiple_of(granularity) && (shadow as usize).is_multiple_of(8) {
+            while addr + 64 * granularity <= end && unsafe { shadow_block_is_zero(shadow) } {
+                addr += 64 * granularity;
+                shadow = shadow.wrapping_add(64);
+            }
+            while addr + 8 * granularity <= end && unsafe { read_shadow_word(shadow) } == 0 {
+                addr += 8 * granularity;
+                shadow = shadow.wrapping_add(8);
+            }
+            if addr >= end {
+                break;
+            }
+        }
+
+        let granule_beg = addr & !(granularity - 1);
+        let granule_end = (granule_beg + granularity).min(end);
+        let shadow =
+            unsafe { read_shadow_byte(mapping.shadow_address(granule_beg as *const c_void)) } as i8;
+        if shadow != 0 {
+            let first_poisoned = if shadow < 0 {
+                addr
+            } else {
+                addr.max(granule_beg + shadow as usize)
+            };
+            if first_poisoned < granule_end {
+                return Some(first_poisoned - beg);
+            }
+        }
+        addr = granule_end;
+    }
+    None
+}
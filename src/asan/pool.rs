@@ -0,0 +1,146 @@
+use super::{poison_memory_region, unpoison_memory_region, update_allocation_context};
+
+use std::fmt;
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::c_void;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A pool of objects (e.g., connections or buffers) that are poisoned while
+/// they are in the pool, so that uses after release are reported by
+/// AddressSanitizer.
+///
+/// Each object is stored in its own heap chunk, whose allocation context is
+/// updated to the current stack trace when the object is checked out, so that
+/// the reports point at the checkout site instead of the original allocation
+/// of the object.
+pub struct PoisonedPool<T> {
+    slots: Mutex<Vec<Box<T>>>,
+}
+
+impl<T> PoisonedPool<T> {
+    /// Creates a new, empty pool.
+    pub const fn new() -> Self {
+        PoisonedPool {
+            slots: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the number of objects in the pool.
+    pub fn len(&self) -> usize {
+        self.lock_slots().len()
+    }
+
+    /// Returns whether the pool contains no objects.
+    pub fn is_empty(&self) -> bool {
+        self.lock_slots().is_empty()
+    }
+
+    /// Adds an object to the pool.
+    pub fn put(&self, value: T) {
+        self.release(Box::new(value));
+    }
+
+    /// Checks out an object from the pool, or returns `None` if the pool is
+    /// empty.
+    pub fn get(&self) -> Option<PoolGuard<'_, T>> {
+        let slot = self.lock_slots().pop()?;
+        Some(self.checkout(slot))
+    }
+
+    /// Checks out an object from the pool, or creates a new one with the given
+    /// function if the pool is empty.
+    pub fn get_or_else(&self, f: impl FnOnce() -> T) -> PoolGuard<'_, T> {
+        match self.lock_slots().pop() {
+            Some(slot) => self.checkout(slot),
+            None => self.checkout(Box::new(f())),
+        }
+    }
+
+    fn lock_slots(&self) -> MutexGuard<'_, Vec<Box<T>>> {
+        self.slots.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn checkout(&self, slot: Box<T>) -> PoolGuard<'_, T> {
+        let ptr = &*slot as *const T as *const c_void;
+        unsafe { unpoison_memory_region(ptr, size_of::<T>()) };
+        unsafe { update_allocation_context(ptr) };
+        PoolGuard {
+            pool: self,
+            slot: Some(slot),
+        }
+    }
+
+    fn release(&self, slot: Box<T>) {
+        unsafe { poison_memory_region(&*slot as *const T as *const c_void, size_of::<T>()) };
+        self.lock_slots().push(slot);
+    }
+}
+
+impl<T> Default for PoisonedPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for PoisonedPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The objects in the pool are poisoned and cannot be formatted.
+        f.debug_struct("PoisonedPool")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Drop for PoisonedPool<T> {
+    fn drop(&mut self) {
+        let slots = self.slots.get_mut().unwrap_or_else(PoisonError::into_inner);
+        for slot in slots.drain(..) {
+            unsafe { unpoison_memory_region(&*slot as *const T as *const c_void, size_of::<T>()) };
+            drop(slot);
+        }
+    }
+}
+
+/// A guard for an object checked out from a `PoisonedPool`, which returns the
+/// object to the pool (and poisons it) when dropped.
+pub struct PoolGuard<'a, T> {
+    pool: &'a PoisonedPool<T>,
+    slot: Option<Box<T>>,
+}
+
+impl<T> PoolGuard<'_, T> {
+    /// Removes the object from the pool (i.e., it is not returned to the pool
+    /// when the guard is dropped).
+    pub fn detach(mut self) -> T {
+        *self.slot.take().unwrap()
+    }
+}
+
+impl<T> Deref for PoolGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.slot.as_ref().unwrap()
+    }
+}
+
+impl<T> DerefMut for PoolGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.slot.as_mut().unwrap()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PoolGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T> Drop for PoolGuard<'_, T> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            self.pool.release(slot);
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use super::{
+    poison_memory_region, unpoison_memory_region, update_allocation_context, SHADOW_GRANULARITY,
+};
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::fmt;
+use std::mem::{align_of, size_of};
+use std::os::raw::c_void;
+use std::ptr::{self, null_mut};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Default size, in bytes, of the left and right redzones.
+const DEFAULT_REDZONE_SIZE: usize = 16;
+
+/// Default maximum size, in bytes, of the quarantine (i.e., the default
+/// `quarantine_size_mb` of AddressSanitizer).
+const DEFAULT_QUARANTINE_SIZE: usize = 256 << 20;
+
+/// Header of a block in the quarantine, stored at the beginning of its left
+/// redzone.
+struct Header {
+    next: *mut Header,
+    layout: Layout,
+}
+
+/// Blocks in the quarantine, from the least to the most recently freed.
+struct Quarantine {
+    head: *mut Header,
+    tail: *mut Header,
+    size: usize,
+}
+
+// SAFETY: The blocks in the quarantine are owned by the allocator and only
+// accessed while the quarantine is locked.
+unsafe impl Send for Quarantine {}
+
+/// An allocator that wraps another allocator (e.g., jemalloc or mimalloc) and
+/// surrounds each allocation with poisoned redzones, so that overflows are
+/// reported by AddressSanitizer.
+///
+/// Freed blocks are kept poisoned in a quarantine of bounded size before being
+/// returned to the inner allocator, so that uses after free are reported as
+/// well. The allocation context of each block is updated to the current stack
+/// trace when the inner allocator is the ASan allocator (e.g., `System`).
+///
+/// E.g.:
+///
+/// ```ignore
+/// #[global_allocator]
+/// static GLOBAL: RedzoneAllocator<Jemalloc> = RedzoneAllocator::new(Jemalloc)
+///     .left_redzone(32)
+///     .quarantine_size(64 << 20);
+/// ```
+///
+/// When built without AddressSanitizer, the redzones and the quarantine only
+/// increase the memory usage.
+pub struct RedzoneAllocator<A: GlobalAlloc> {
+    inner: A,
+    left_redzone: usize,
+    right_redzone: usize,
+    quarantine_size: usize,
+    quarantine: Mutex<Quarantine>,
+}
+
+impl<A: GlobalAlloc> RedzoneAllocator<A> {
+    /// Creates a new allocator that wraps the given allocator, with redzones of
+    /// 16 bytes and a quarantine of 256 MB.
+    pub const fn new(inner: A) -> Self {
+        RedzoneAllocator {
+            inner,
+            left_redzone: DEFAULT_REDZONE_SIZE,
+            right_redzone: DEFAULT_REDZONE_SIZE,
+            quarantine_size: DEFAULT_QUARANTINE_SIZE,
+            quarantine: Mutex::new(Quarantine {
+                head: null_mut(),
+                tail: null_mut(),
+                size: 0,
+            }),
+        }
+    }
+
+    /// Sets the minimum size, in bytes, of the redzone before each allocation.
+    ///
+    /// The redzone is enlarged to hold the quarantine bookkeeping and to keep
+    /// the allocation aligned.
+    pub const fn left_redzone(mut self, size: usize) -> Self {
+        self.left_redzone = size;
+        self
+    }
+
+    /// Sets the minimum size, in bytes, of the redzone after each allocation.
+    pub const fn right_redzone(mut self, size: usize) -> Self {
+        self.right_redzone = size;
+        self
+    }
+
+    /// Sets the maximum size, in bytes, of the quarantine of freed blocks
+    /// (including their redzones). A size of 0 disables the quarantine.
+    pub const fn quarantine_size(mut self, size: usize) -> Self {
+        self.quarantine_size = size;
+        self
+    }
+
+    /// Returns the inner allocator.
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// Returns the total size, in bytes, of the blocks in the quarantine.
+    pub fn quarantined_size(&self) -> usize {
+        self.lock_quarantine().size
+    }
+
+    /// Returns all the blocks in the quarantine to the inner allocator.
+    pub fn flush_quarantine(&self) {
+        let mut quarantine = self.lock_quarantine();
+        let head = quarantine.head;
+        quarantine.head = null_mut();
+        quarantine.tail = null_mut();
+        quarantine.size = 0;
+        drop(quarantine);
+        unsafe { self.release_all(head) };
+    }
+
+    fn lock_quarantine(&self) -> MutexGuard<'_, Quarantine> {
+        self.quarantine
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the layout of the block for an allocation and the offset of the
+    /// allocation in the block, or `None` if the size of the block overflows.
+    fn block_layout(&self, layout: Layout) -> Option<(Layout, usize)> {
+        let align = layout
+            .align()
+            .max(SHADOW_GRANULARITY)
+            .max(align_of::<Header>());
+        let offset = self
+            .left_redzone
+            .max(size_of::<Header>())
+            .checked_next_multiple_of(align)?;
+        let size = offset
+            .checked_add(layout.size())?
+            .checked_add(self.right_redzone)?;
+        let block_layout = Layout::from_size_align(size, align).ok()?;
+        Some((block_layout.pad_to_align(), offset))
+    }
+
+    /// Returns a block to the inner allocator.
+    unsafe fn release(&self, block: *mut u8, layout: Layout) {
+        unsafe {
+            unpoison_memory_region(block as *const c_void, layout.size());
+            self.inner.dealloc(block, layout);
+        }
+    }
+
+    /// Returns a list of blocks removed from the quarantine to the inner
+    /// allocator.
+    unsafe fn release_all(&self, mut header: *mut Header) {
+        while !header.is_null() {
+            let (next, layout) =
+                unsafe { with_header(header, |header| (header.next, header.layout)) };
+            unsafe { self.release(header as *mut u8, layout) };
+            header = next;
+        }
+    }
+}
+
+/// Calls `f` with the header of a block in the quarantine temporarily
+/// unpoisoned.
+unsafe fn with_header<R>(header: *mut Header, f: impl FnOnce(&mut Header) -> R) -> R {
+    unsafe {
+        unpoison_memory_region(header as *const c_void, size_of::<Header>());
+        let result = f(&mut *header);
+        poison_memory_region(header as *const c_void, size_of::<Header>());
+        result
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for RedzoneAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let Some((block_layout, offset)) = self.block_layout(layout) else {
+            return null_mut();
+        };
+        let block = unsafe { self.inner.alloc(block_layout) };
+        if block.is_null() {
+            return block;
+        }
+        unsafe {
+            let ptr = block.add(offset);
+            poison_memory_region(block as *const c_void, offset);
+            poison_memory_region(
+                ptr.add(layout.size()) as *const c_void,
+                block_layout.size() - offset - layout.size(),
+            );
+            update_allocation_context(ptr as *const c_void);
+            ptr
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // The layout is the one used for the allocation, whose block layout
+        // did not overflow.
+        let Some((block_layout, offset)) = self.block_layout(layout) else {
+            return;
+        };
+        let block = unsafe { ptr.sub(offset) };
+        if block_layout.size() > self.quarantine_size {
+            unsafe { self.release(block, block_layout) };
+            return;
+        }
+
+        let header = block as *mut Header;
+        unsafe {
+            unpoison_memory_region(header as *const c_void, size_of::<Header>());
+            ptr::write(
+                header,
+                Header {
+                    next: null_mut(),
+                    layout: block_layout,
+                },
+            );
+            poison_memory_region(block as *const c_void, block_layout.size());
+        }
+
+        let mut quarantine = self.lock_quarantine();
+        if quarantine.tail.is_null() {
+            quarantine.head = header;
+        } else {
+            unsafe { with_header(quarantine.tail, |tail| tail.next = header) };
+        }
+        quarantine.tail = header;
+        quarantine.size += block_layout.size();
+
+        // Evict the least recently freed blocks, and return them to the inner
+        // allocator after unlocking the quarantine.
+        let evicted = quarantine.head;
+        let mut last = null_mut();
+        while quarantine.size > self.quarantine_size {
+            last = quarantine.head;
+            let (next, layout) =
+                unsafe { with_header(last, |header| (header.next, header.layout)) };
+            quarantine.head = next;
+            quarantine.size -= layout.size();
+        }
+        if last.is_null() {
+            return;
+        }
+        if quarantine.head.is_null() {
+            quarantine.tail = null_mut();
+        }
+        unsafe { with_header(last, |header| header.next = null_mut()) };
+        drop(quarantine);
+        unsafe { self.release_all(evicted) };
+    }
+}
+
+impl<A: GlobalAlloc + fmt::Debug> fmt::Debug for RedzoneAllocator<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedzoneAllocator")
+            .field("inner", &self.inner)
+            .field("left_redzone", &self.left_redzone)
+            .field("right_redzone", &self.right_redzone)
+            .field("quarantine_size", &self.quarantine_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: GlobalAlloc> Drop for RedzoneAllocator<A> {
+    fn drop(&mut self) {
+        self.flush_quarantine();
+    }
+}
@@ -0,0 +1,419 @@
+use crate::ffi::common::*;
+
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Mutex, PoisonError};
+
+/// Prints the stack trace leading to this call to stderr (or the report file).
+pub fn print_stack_trace() {
+    unsafe {
+        __sanitizer_print_stack_trace();
+    }
+}
+
+/// Sets the path prefix of the report file (i.e., reports are written to
+/// `path.<pid>` instead of stderr). The special values `stdout` and `stderr`
+/// are also accepted.
+pub fn set_report_path(path: &str) {
+    let path_cstr = CString::new(path).unwrap();
+    unsafe {
+        __sanitizer_set_report_path(path_cstr.as_ptr());
+    }
+}
+
+/// Sets the file descriptor reports are written to.
+pub fn set_report_fd(fd: c_int) {
+    unsafe {
+        __sanitizer_set_report_fd(fd as isize as *mut c_void);
+    }
+}
+
+/// Gets the full path of the current report file, or `None` if reports are not
+/// written to a file set with `set_report_path`.
+pub fn get_report_path() -> Option<String> {
+    unsafe {
+        let path_ptr = __sanitizer_get_report_path();
+        if path_ptr.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(path_ptr).to_string_lossy().into_owned())
+        }
+    }
+}
+
+/// Arguments for `sandbox_on_notify`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SandboxArguments {
+    /// Whether to enable sandbox support in sanitizer coverage.
+    pub coverage_sandboxed: bool,
+    /// File descriptor to write coverage data to, or -1 to have a file
+    /// pre-opened.
+    pub coverage_fd: isize,
+    /// Maximum size of the blocks the coverage data is split into (e.g., when
+    /// the file descriptor is a socket), or 0 to not split it.
+    pub coverage_max_block_size: u32,
+}
+
+impl Default for SandboxArguments {
+    fn default() -> Self {
+        SandboxArguments {
+            coverage_sandboxed: false,
+            coverage_fd: -1,
+            coverage_max_block_size: 0,
+        }
+    }
+}
+
+/// Notifies the sanitizer runtime that the process is about to enter a sandbox
+/// (e.g., so that it caches the memory mappings and opens the files it needs
+/// while it still can).
+pub fn sandbox_on_notify(args: &SandboxArguments) {
+    let mut args = __sanitizer_sandbox_arguments {
+        coverage_sandboxed: args.coverage_sandboxed as c_int,
+        coverage_fd: args.coverage_fd,
+        coverage_max_block_size: args.coverage_max_block_size,
+    };
+    unsafe {
+        __sanitizer_sandbox_on_notify(&mut args);
+    }
+}
+
+/// Acquires the crash state of the process (i.e., returns `true` on the first
+/// call and `false` thereafter), so that only one report is printed when
+/// multiple errors occur simultaneously.
+pub fn acquire_crash_state() -> bool {
+    unsafe { __sanitizer_acquire_crash_state() != 0 }
+}
+
+/// Gets the path of the module (i.e., the executable or shared library) that
+/// contains the given PC (program counter) and the offset of the PC in the
+/// module, or `None` if the PC does not belong to a known module.
+///
+/// The runtime only looks up the PC in the list of loaded modules and never
+/// dereferences it, so any address (including null) can be looked up.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn get_module_and_offset_for_pc(pc: *const c_void) -> Option<(String, usize)> {
+    const BUFFER_SIZE: usize = 4096;
+    let mut module_path = vec![0 as c_char; BUFFER_SIZE];
+    let mut pc_offset: *mut c_void = std::ptr::null_mut();
+
+    unsafe {
+        let found = __sanitizer_get_module_and_offset_for_pc(
+            pc as *mut c_void,
+            module_path.as_mut_ptr(),
+            module_path.len(),
+            &mut pc_offset,
+        );
+        if found == 0 {
+            return None;
+        }
+        let module_path = CStr::from_ptr(module_path.as_ptr())
+            .to_string_lossy()
+            .into_owned();
+        Some((module_path, pc_offset as usize))
+    }
+}
+
+/// Struct to hold a stack trace (i.e., the return addresses of its frames, with
+/// the innermost frame first).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StackTrace {
+    frames: Vec<*mut c_void>,
+}
+
+// SAFETY: The return addresses are never dereferenced.
+unsafe impl Send for StackTrace {}
+unsafe impl Sync for StackTrace {}
+
+impl StackTrace {
+    /// Creates a stack trace from the return addresses of its frames, up to the
+    /// first null address (i.e., the padding of the buffers filled by the
+    /// sanitizer runtimes is removed).
+    ///
+    /// The return addresses are never dereferenced, so any addresses (including
+    /// null) can be given.
+    pub fn new(mut frames: Vec<*mut c_void>) -> Self {
+        if let Some(len) = frames.iter().position(|frame| frame.is_null()) {
+            frames.truncate(len);
+        }
+        frames.shrink_to_fit();
+        StackTrace { frames }
+    }
+
+    /// Returns the return addresses of the frames.
+    pub fn frames(&self) -> &[*mut c_void] {
+        &self.frames
+    }
+
+    /// Returns the number of frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns whether the stack trace has no frames.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Returns an iterator over the return addresses of the frames.
+    pub fn iter(&self) -> std::iter::Copied<std::slice::Iter<'_, *mut c_void>> {
+        self.frames.iter().copied()
+    }
+
+    /// Symbolizes the frames with the symbolizer of the sanitizer runtime (see
+    /// `symbolize`), including the inlined frames.
+    pub fn symbolize(&self) -> Vec<SymbolizedFrame> {
+        self.iter().flat_map(|pc| symbolize(pc)).collect()
+    }
+}
+
+impl IntoIterator for StackTrace {
+    type Item = *mut c_void;
+    type IntoIter = std::vec::IntoIter<*mut c_void>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a StackTrace {
+    type Item = *mut c_void;
+    type IntoIter = std::iter::Copied<std::slice::Iter<'a, *mut c_void>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl From<Vec<*mut c_void>> for StackTrace {
+    fn from(frames: Vec<*mut c_void>) -> Self {
+        StackTrace::new(frames)
+    }
+}
+
+impl fmt::Display for StackTrace {
+    /// Formats the stack trace like the sanitizer runtimes do (i.e., one line
+    /// per frame, including the inlined frames), symbolized when possible.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut index = 0;
+        for pc in self {
+            let frames = symbolize(pc);
+            if frames.is_empty() {
+                if index > 0 {
+                    writeln!(f)?;
+                }
+                write!(f, "    #{} {:p}", index, pc)?;
+                index += 1;
+            }
+            for frame in frames {
+                if index > 0 {
+                    writeln!(f)?;
+                }
+                write!(f, "    #{} {:p} in {}", index, pc, frame)?;
+                index += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Separator of the fields of the symbolization formats (i.e., a character that
+/// does not occur in function names or paths).
+const FIELD_SEPARATOR: char = '\x1f';
+
+/// Value printed by the runtime for unknown strings.
+const UNKNOWN: &str = "<null>";
+
+/// A frame of a symbolized PC (program counter).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SymbolizedFrame {
+    /// Demangled name of the function.
+    pub function: Option<String>,
+    /// Path of the source file.
+    pub file: Option<String>,
+    /// Line in the source file.
+    pub line: Option<u32>,
+    /// Column in the source file.
+    pub column: Option<u32>,
+    /// Path of the module (i.e., the executable or shared library).
+    pub module: Option<String>,
+    /// Offset of the PC in the module.
+    pub offset: Option<usize>,
+    /// Whether the frame was inlined into the next frame.
+    pub inlined: bool,
+}
+
+impl fmt::Display for SymbolizedFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.function.as_deref().unwrap_or("??"))?;
+        match (&self.file, self.line, self.column) {
+            (Some(file), Some(line), Some(column)) => write!(f, " {}:{}:{}", file, line, column),
+            (Some(file), Some(line), None) => write!(f, " {}:{}", file, line),
+            (Some(file), None, _) => write!(f, " {}", file),
+            (None, ..) => match (&self.module, self.offset) {
+                (Some(module), Some(offset)) => write!(f, " ({}+{:#x})", module, offset),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+/// A symbolized global variable.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SymbolizedGlobal {
+    /// Demangled name of the global variable.
+    pub name: String,
+    /// Path of the source file.
+    pub file: Option<String>,
+    /// Line in the source file.
+    pub line: Option<u32>,
+}
+
+/// Symbolizes the given PC (program counter) with the symbolizer of the
+/// sanitizer runtime, and returns its frames, starting with the innermost
+/// inlined frame, or an empty vector if the PC does not belong to a known
+/// module.
+///
+/// The PC is expected to be a return address (e.g., an element of a stack trace
+/// returned by a sanitizer), since the instruction before it is symbolized.
+/// The runtime never dereferences the PC, so any address (including null) can
+/// be symbolized.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn symbolize(pc: *const c_void) -> Vec<SymbolizedFrame> {
+    let format = ["%f", "%s", "%l", "%c", "%m", "%o"].join(&FIELD_SEPARATOR.to_string());
+    let output = symbolize_with(&format, |format, out_buf, out_buf_size| unsafe {
+        __sanitizer_symbolize_pc(pc as *mut c_void, format, out_buf, out_buf_size);
+    });
+    let mut frames: Vec<SymbolizedFrame> = output
+        .split('\0')
+        .take_while(|frame| !frame.is_empty())
+        .filter_map(|frame| {
+            let mut fields = frame.split(FIELD_SEPARATOR);
+            let mut next_field = || fields.next().filter(|field| *field != UNKNOWN);
+            let frame = SymbolizedFrame {
+                function: next_field().map(str::to_string),
+                file: next_field().map(str::to_string),
+                line: next_field()
+                    .and_then(|line| line.parse().ok())
+                    .filter(|line| *line != 0),
+                column: next_field()
+                    .and_then(|column| column.parse().ok())
+                    .filter(|column| *column != 0),
+                module: next_field().map(str::to_string),
+                offset: next_field().and_then(|offset| {
+                    usize::from_str_radix(offset.trim_start_matches("0x"), 16).ok()
+                }),
+                inlined: false,
+            };
+            frame.module.as_ref()?;
+            Some(frame)
+        })
+        .collect();
+    if let Some((_, inlined_frames)) = frames.split_last_mut() {
+        for frame in inlined_frames {
+            frame.inlined = true;
+        }
+    }
+    frames
+}
+
+/// Symbolizes the global variable that contains the given address with the
+/// symbolizer of the sanitizer runtime, or returns `None` if the address does
+/// not belong to a global variable.
+///
+/// The runtime never dereferences the address, so any address (including null)
+/// can be symbolized.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn symbolize_global(addr: *const c_void) -> Option<SymbolizedGlobal> {
+    let format = ["%g", "%s", "%l"].join(&FIELD_SEPARATOR.to_string());
+    let output = symbolize_with(&format, |format, out_buf, out_buf_size| unsafe {
+        __sanitizer_symbolize_global(addr as *mut c_void, format, out_buf, out_buf_size);
+    });
+    let mut fields = output.split('\0').next()?.split(FIELD_SEPARATOR);
+    let name = fields.next().filter(|name| !name.is_empty())?.to_string();
+    let file = fields
+        .next()
+        .map(str::trim)
+        .filter(|file| !file.is_empty() && *file != UNKNOWN)
+        .map(str::to_string);
+    let line = fields
+        .next()
+        .and_then(|line| line.parse().ok())
+        .filter(|line| *line != 0);
+    Some(SymbolizedGlobal { name, file, line })
+}
+
+/// Calls the given symbolization function with buffers of increasing size until
+/// the output is not truncated, and returns the output.
+fn symbolize_with(format: &str, f: impl Fn(*const c_char, *mut c_char, usize)) -> String {
+    const INITIAL_BUFFER_SIZE: usize = 4096;
+    const MAX_BUFFER_SIZE: usize = 1 << 20;
+    let format_cstr = CString::new(format).unwrap();
+    let mut buffer_size = INITIAL_BUFFER_SIZE;
+    loop {
+        let mut buffer = vec![0 as c_char; buffer_size];
+        f(format_cstr.as_ptr(), buffer.as_mut_ptr(), buffer.len());
+        // The output is terminated by an empty string, so it was not truncated
+        // if the last two bytes before the null terminator written by the
+        // runtime are null.
+        if (buffer[buffer_size - 3] == 0 && buffer[buffer_size - 2] == 0)
+            || buffer_size >= MAX_BUFFER_SIZE
+        {
+            let bytes: Vec<u8> = buffer.iter().map(|&byte| byte as u8).collect();
+            return String::from_utf8_lossy(&bytes).into_owned();
+        }
+        buffer_size *= 2;
+    }
+}
+
+type DeathCallback = Box<dyn FnOnce() + Send>;
+
+// The sanitizer runtimes have a single death callback (i.e.,
+// `__sanitizer_set_death_callback`, `__asan_set_death_callback`, and
+// `__msan_set_death_callback` all set the same callback), so the closures set
+// with any of the wrappers share a single registry.
+static DEATH_CALLBACK: Mutex<Option<DeathCallback>> = Mutex::new(None);
+
+pub(crate) unsafe extern "C" fn death_callback() {
+    let callback = DEATH_CALLBACK
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    if let Some(callback) = callback {
+        // Unwinding into the runtime is not allowed.
+        let _ = panic::catch_unwind(AssertUnwindSafe(callback));
+    }
+}
+
+pub(crate) fn store_death_callback(callback: Option<DeathCallback>) {
+    *DEATH_CALLBACK
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = callback;
+}
+
+/// Sets a callback to be called right before the sanitizer runtime terminates
+/// the process after reporting an error (e.g., to flush buffers or write crash
+/// artifacts).
+///
+/// The runtime has a single death callback, so the callback replaces any
+/// callback previously set (including with `asan::set_death_callback` or
+/// `msan::set_death_callback`). Panics in the callback are caught and ignored.
+pub fn set_death_callback<F>(callback: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    store_death_callback(Some(Box::new(callback)));
+    unsafe {
+        __sanitizer_set_death_callback(Some(death_callback));
+    }
+}
+
+/// Clears the callback set with `set_death_callback`.
+pub fn clear_death_callback() {
+    unsafe {
+        __sanitizer_set_death_callback(None);
+    }
+    store_death_callback(None);
+}
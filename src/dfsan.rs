@@ -1,10 +1,7 @@
-/// DataFlowSanitizer interface.
-///
-/// For more information about DataFlowSanitizer, see
-/// https://clang.llvm.org/docs/DataFlowSanitizer.html.
 use crate::ffi::dfsan::*;
 
 use std::ffi::{CStr, CString};
+use std::mem::size_of_val;
 use std::os::raw::{c_char, c_int, c_long, c_uint, c_void};
 
 pub type DfsanLabel = u8;
@@ -16,20 +13,45 @@ pub fn union(l1: DfsanLabel, l2: DfsanLabel) -> DfsanLabel {
 }
 
 /// Sets the label for each address in `[addr, addr+size)` to `label`.
-pub fn set_label(label: DfsanLabel, addr: *mut c_void, size: usize) {
+///
+/// # Safety
+///
+/// `addr` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn set_label(label: DfsanLabel, addr: *mut c_void, size: usize) {
     unsafe {
         dfsan_set_label(label, addr, size);
     }
 }
 
+/// Sets the label for each byte of `value` (e.g., a slice) to `label`.
+pub fn set_label_val<T: ?Sized>(label: DfsanLabel, value: &mut T) {
+    unsafe {
+        set_label(label, value as *mut T as *mut c_void, size_of_val(value));
+    }
+}
+
 /// Sets the label for each address in `[addr, addr+size)` to the union of the
 /// current label for that address and `label`.
-pub fn add_label(label: DfsanLabel, addr: *mut c_void, size: usize) {
+///
+/// # Safety
+///
+/// `addr` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn add_label(label: DfsanLabel, addr: *mut c_void, size: usize) {
     unsafe {
         dfsan_add_label(label, addr, size);
     }
 }
 
+/// Sets the label for each byte of `value` (e.g., a slice) to the union of the
+/// current label for that byte and `label`.
+pub fn add_label_val<T: ?Sized>(label: DfsanLabel, value: &mut T) {
+    unsafe {
+        add_label(label, value as *mut T as *mut c_void, size_of_val(value));
+    }
+}
+
 /// Retrieves the label associated with the given data.
 pub fn get_label(data: c_long) -> DfsanLabel {
     unsafe { dfsan_get_label(data) }
@@ -41,16 +63,37 @@ pub fn get_origin(data: c_long) -> DfsanOrigin {
 }
 
 /// Retrieves the label associated with the data at the given address.
-pub fn read_label(addr: *const c_void, size: usize) -> DfsanLabel {
+///
+/// # Safety
+///
+/// `addr` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn read_label(addr: *const c_void, size: usize) -> DfsanLabel {
     unsafe { dfsan_read_label(addr, size) }
 }
 
+/// Retrieves the label associated with the memory of `value`.
+pub fn read_label_val<T: ?Sized>(value: &T) -> DfsanLabel {
+    unsafe { read_label(value as *const T as *const c_void, size_of_val(value)) }
+}
+
 /// Return the origin associated with the first taint byte in the size bytes
 /// from the address `addr`.
-pub fn read_origin_of_first_taint(addr: *const c_void, size: usize) -> DfsanOrigin {
+///
+/// # Safety
+///
+/// `addr` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn read_origin_of_first_taint(addr: *const c_void, size: usize) -> DfsanOrigin {
     unsafe { dfsan_read_origin_of_first_taint(addr, size) }
 }
 
+/// Return the origin associated with the first taint byte in the memory of
+/// `value`.
+pub fn read_origin_of_first_taint_val<T: ?Sized>(value: &T) -> DfsanOrigin {
+    unsafe { read_origin_of_first_taint(value as *const T as *const c_void, size_of_val(value)) }
+}
+
 /// Returns whether the given label contains the label `elem`.
 pub fn has_label(label: DfsanLabel, elem: DfsanLabel) -> bool {
     unsafe { dfsan_has_label(label, elem) != 0 }
@@ -114,7 +157,11 @@ pub fn get_labels_in_signal_reaches_function() -> DfsanLabel {
 }
 
 /// Prints the origin trace of the label at the address `addr` to stderr.
-pub fn print_origin_trace(addr: *const c_void, description: Option<&str>) {
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+pub unsafe fn print_origin_trace(addr: *const c_void, description: Option<&str>) {
     let description_cstr = description.map(|s| CString::new(s).unwrap());
 
     unsafe {
@@ -127,9 +174,20 @@ pub fn print_origin_trace(addr: *const c_void, description: Option<&str>) {
     }
 }
 
+/// Prints the origin trace of the label of `value` to stderr.
+pub fn print_origin_trace_val<T: ?Sized>(value: &T, description: Option<&str>) {
+    unsafe {
+        print_origin_trace(value as *const T as *const c_void, description);
+    }
+}
+
 /// Prints the origin trace of the label at the address `addr` to a
 /// pre-allocated output buffer.
-pub fn sprint_origin_trace(
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+pub unsafe fn sprint_origin_trace(
     addr: *const c_void,
     description: Option<&str>,
 ) -> Result<String, String> {
@@ -165,6 +223,15 @@ pub fn sprint_origin_trace(
     }
 }
 
+/// Prints the origin trace of the label of `value` to a pre-allocated output
+/// buffer.
+pub fn sprint_origin_trace_val<T: ?Sized>(
+    value: &T,
+    description: Option<&str>,
+) -> Result<String, String> {
+    unsafe { sprint_origin_trace(value as *const T as *const c_void, description) }
+}
+
 /// Prints the stack trace leading to this call to a pre-allocated output
 /// buffer.
 pub fn sprint_stack_trace() -> Result<String, String> {
@@ -187,10 +254,19 @@ pub fn sprint_stack_trace() -> Result<String, String> {
 
 /// Retrieves the very first origin associated with the data at the given
 /// address.
-pub fn get_init_origin(addr: *const c_void) -> DfsanOrigin {
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+pub unsafe fn get_init_origin(addr: *const c_void) -> DfsanOrigin {
     unsafe { dfsan_get_init_origin(addr) }
 }
 
+/// Retrieves the very first origin associated with the memory of `value`.
+pub fn get_init_origin_val<T: ?Sized>(value: &T) -> DfsanOrigin {
+    unsafe { get_init_origin(value as *const T as *const c_void) }
+}
+
 /// Returns the value of -dfsan-track-origins.
 pub fn get_track_origins() -> c_int {
     unsafe { dfsan_get_track_origins() }
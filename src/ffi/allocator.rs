@@ -0,0 +1,112 @@
+use std::os::raw::{c_int, c_void};
+
+#[cfg(any(
+    sanitize_address,
+    sanitize_dataflow,
+    sanitize_leak,
+    sanitize_memory,
+    sanitize_thread
+))]
+extern "C" {
+    /// Returns the estimated number of bytes that will be reserved by allocator
+    /// for request of "size" bytes. If allocator can't allocate that much
+    /// memory, returns the maximal possible allocation size, otherwise returns
+    /// "size".
+    pub fn __sanitizer_get_estimated_allocated_size(size: usize) -> usize;
+    /// Returns true if p was returned by the allocator and
+    /// is not yet freed.
+    pub fn __sanitizer_get_ownership(p: *const c_void) -> c_int;
+    /// If a pointer lies within an allocation, it will return the start address
+    /// of the allocation. Otherwise, it returns nullptr.
+    pub fn __sanitizer_get_allocated_begin(p: *const c_void) -> *const c_void;
+    /// Returns the number of bytes reserved for the pointer p.
+    /// Requires (get_ownership(p) == true) or (p == 0).
+    pub fn __sanitizer_get_allocated_size(p: *const c_void) -> usize;
+    /// Returns the number of bytes reserved for the pointer p.
+    /// Requires __sanitizer_get_allocated_begin(p) == p.
+    pub fn __sanitizer_get_allocated_size_fast(p: *const c_void) -> usize;
+    /// Number of bytes, allocated and not yet freed by the application.
+    pub fn __sanitizer_get_current_allocated_bytes() -> usize;
+    /// Number of bytes, mmaped by the allocator to fulfill allocation requests.
+    /// Generally, for request of X bytes, allocator can reserve and add to free
+    /// lists a large number of chunks of size X to use them for future requests.
+    /// All these chunks count toward the heap size. Currently, allocator never
+    /// releases memory to OS (instead, it just puts freed chunks to free
+    /// lists).
+    pub fn __sanitizer_get_heap_size() -> usize;
+    /// Number of bytes, mmaped by the allocator, which can be used to fulfill
+    /// allocation requests. When a user program frees memory chunk, it can first
+    /// fall into quarantine and will count toward __sanitizer_get_free_bytes()
+    /// later.
+    pub fn __sanitizer_get_free_bytes() -> usize;
+    /// Number of bytes in unmapped pages, that are released to OS. Currently,
+    /// always returns 0.
+    pub fn __sanitizer_get_unmapped_bytes() -> usize;
+}
+
+#[cfg(any(sanitize_address, sanitize_leak, sanitize_memory, sanitize_thread))]
+extern "C" {
+    /// Drains allocator quarantines (calling thread's and global ones), returns
+    /// freed memory back to OS and releases other non-essential internal
+    /// allocator resources in attempt to reduce process RSS.
+    /// Currently available with ASan only.
+    pub fn __sanitizer_purge_allocator();
+}
+
+/// No-op fallback for __sanitizer_purge_allocator, which the DataFlowSanitizer
+/// runtime does not provide.
+#[cfg(sanitize_dataflow)]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn __sanitizer_purge_allocator() {}
+
+#[cfg(not(any(
+    sanitize_address,
+    sanitize_dataflow,
+    sanitize_leak,
+    sanitize_memory,
+    sanitize_thread
+)))]
+pub use self::noop::*;
+
+/// No-op fallbacks for the allocator interface, used when the crate is built
+/// without any sanitizer enabled.
+#[cfg(not(any(
+    sanitize_address,
+    sanitize_dataflow,
+    sanitize_leak,
+    sanitize_memory,
+    sanitize_thread
+)))]
+#[allow(clippy::missing_safety_doc)]
+mod noop {
+    use super::*;
+
+    pub unsafe fn __sanitizer_get_estimated_allocated_size(size: usize) -> usize {
+        size
+    }
+    pub unsafe fn __sanitizer_get_ownership(_p: *const c_void) -> c_int {
+        0
+    }
+    pub unsafe fn __sanitizer_get_allocated_begin(_p: *const c_void) -> *const c_void {
+        std::ptr::null()
+    }
+    pub unsafe fn __sanitizer_get_allocated_size(_p: *const c_void) -> usize {
+        0
+    }
+    pub unsafe fn __sanitizer_get_allocated_size_fast(_p: *const c_void) -> usize {
+        0
+    }
+    pub unsafe fn __sanitizer_get_current_allocated_bytes() -> usize {
+        0
+    }
+    pub unsafe fn __sanitizer_get_heap_size() -> usize {
+        0
+    }
+    pub unsafe fn __sanitizer_get_free_bytes() -> usize {
+        0
+    }
+    pub unsafe fn __sanitizer_get_unmapped_bytes() -> usize {
+        0
+    }
+    pub unsafe fn __sanitizer_purge_allocator() {}
+}
@@ -1,10 +1,7 @@
-/// FFI bindings for the AddressSanitizer interface.
-///
-/// For more information about AddressSanitizer, see
-/// https://clang.llvm.org/docs/AddressSanitizer.html.
 use std::option::Option;
 use std::os::raw::{c_char, c_int, c_void};
 
+#[cfg(sanitize_address)]
 extern "C" {
     /// Marks a memory region (<c>[addr, addr+size)</c>) as unaddressable.
     ///
@@ -273,4 +270,313 @@ extern "C" {
     /// Update allocation stack trace for the given allocation to the current stack
     /// trace. Returns 1 if successful, 0 if not.
     pub fn __asan_update_allocation_context(addr: *mut c_void) -> c_int;
+    /// Annotates the current state of a contiguous container, such as
+    /// <c>std::vector</c>, <c>std::string</c>, or similar.
+    ///
+    /// A contiguous container is a container that keeps all of its elements
+    /// in a contiguous region of memory. The container owns the region of memory
+    /// <c>[beg, end)</c>; the memory <c>[beg, mid)</c> is used to store the
+    /// current elements, and the memory <c>[mid, end)</c> is reserved for future
+    /// elements (<c>beg <= mid <= end</c>). For example, in
+    /// <c>std::vector<> v</c>:
+    ///
+    /// \code
+    ///   beg = &v[0];
+    ///   end = beg + v.capacity() * sizeof(v[0]);
+    ///   mid = beg + v.size()     * sizeof(v[0]);
+    /// \endcode
+    ///
+    /// This annotation tells the Sanitizer tool about the current state of the
+    /// container so that the tool can report errors when memory from
+    /// <c>[mid, end)</c> is accessed. Insert this annotation into methods like
+    /// <c>push_back()</c> or <c>pop_back()</c>. Supply the old and new values of
+    /// <c>mid</c>(<c><i>old_mid</i></c> and <c><i>new_mid</i></c>). In the
+    /// initial state <c>mid == end</c>, so that should be the final state when
+    /// the container is destroyed or when the container reallocates the storage.
+    ///
+    /// For ASan, <c><i>beg</i></c> no longer needs to be 8-aligned,
+    /// first and last granule may be shared with other objects
+    /// and therefore the function can be used for any allocator.
+    ///
+    /// \param beg Beginning of memory region.
+    /// \param end End of memory region.
+    /// \param old_mid Old middle of memory region.
+    /// \param new_mid New middle of memory region.
+    pub fn __sanitizer_annotate_contiguous_container(
+        beg: *const c_void,
+        end: *const c_void,
+        old_mid: *const c_void,
+        new_mid: *const c_void,
+    );
+    /// Returns true if the contiguous container <c>[beg, end)</c> is properly
+    /// poisoned.
+    ///
+    /// Proper poisoning could occur, for example, with
+    /// <c>__sanitizer_annotate_contiguous_container</c>), that is, if
+    /// <c>[beg, mid)</c> is addressable and <c>[mid, end)</c> is unaddressable.
+    /// Full verification requires O (<c>end - beg</c>) time; this function tries
+    /// to avoid such complexity by touching only parts of the container around
+    /// <c><i>beg</i></c>, <c><i>mid</i></c>, and <c><i>end</i></c>.
+    ///
+    /// \param beg Beginning of memory region.
+    /// \param mid Middle of memory region.
+    /// \param end Old end of memory region.
+    ///
+    /// \returns True if the contiguous container <c>[beg, end)</c> is properly
+    ///  poisoned.
+    pub fn __sanitizer_verify_contiguous_container(
+        beg: *const c_void,
+        mid: *const c_void,
+        end: *const c_void,
+    ) -> c_int;
+    /// Similar to <c>__sanitizer_verify_contiguous_container()</c> but also
+    /// returns the address of the first improperly poisoned byte.
+    ///
+    /// Returns NULL if the area is poisoned properly.
+    ///
+    /// \param beg Beginning of memory region.
+    /// \param mid Middle of memory region.
+    /// \param end Old end of memory region.
+    ///
+    /// \returns The bad address or NULL.
+    pub fn __sanitizer_contiguous_container_find_bad_address(
+        beg: *const c_void,
+        mid: *const c_void,
+        end: *const c_void,
+    ) -> *const c_void;
+    /// Annotates the current state of a double-ended contiguous container,
+    /// such as <c>std::deque</c>'s single chunk, when the boundaries are moved.
+    ///
+    /// A contiguous chunk is a chunk that keeps all of its elements
+    /// in a contiguous region of memory. The container owns the region of memory
+    /// <c>[storage_beg, storage_end)</c>; the memory <c>[container_beg,
+    /// container_end)</c> is used to store the current elements, and the memory
+    /// <c>[storage_beg, container_beg), [container_end, storage_end)</c> is
+    /// reserved for future elements (<c>storage_beg <= container_beg <=
+    /// container_end <= storage_end</c>). For example, in <c> std::deque </c>:
+    /// - chunk with a first deque's element will have container_beg equal to
+    ///   address of the first element.
+    /// - in every next chunk with elements, true is <c> container_beg ==
+    ///   storage_beg </c>.
+    ///
+    /// Argument requirements:
+    ///
+    /// During unpoisoning memory of empty container (before first element is
+    /// added):
+    /// - old_container_beg_p == old_container_end_p
+    ///
+    /// During poisoning after last element was removed:
+    /// - new_container_beg_p == new_container_end_p
+    ///
+    /// \param storage_beg Beginning of memory region.
+    /// \param storage_end End of memory region.
+    /// \param old_container_beg Old beginning of used region.
+    /// \param old_container_end End of used region.
+    /// \param new_container_beg New beginning of used region.
+    /// \param new_container_end New end of used region.
+    pub fn __sanitizer_annotate_double_ended_contiguous_container(
+        storage_beg: *const c_void,
+        storage_end: *const c_void,
+        old_container_beg: *const c_void,
+        old_container_end: *const c_void,
+        new_container_beg: *const c_void,
+        new_container_end: *const c_void,
+    );
+    /// Returns true if the double ended contiguous
+    /// container <c>[storage_beg, storage_end)</c> is properly poisoned.
+    ///
+    /// Proper poisoning could occur, for example, with
+    /// <c>__sanitizer_annotate_double_ended_contiguous_container</c>), that is,
+    /// if <c>[storage_beg, container_beg)</c> is not addressable,
+    /// <c>[container_beg, container_end)</c> is addressable and
+    /// <c>[container_end, end)</c> is unaddressable. Full verification requires
+    /// O (<c>storage_end - storage_beg</c>) time; this function tries to avoid
+    /// such complexity by touching only parts of the container around
+    /// <c><i>storage_beg</i></c>, <c><i>container_beg</i></c>,
+    /// <c><i>container_end</i></c>, and <c><i>storage_end</i></c>.
+    ///
+    /// \param storage_beg Beginning of memory region.
+    /// \param container_beg Beginning of used region.
+    /// \param container_end End of used region.
+    /// \param storage_end End of memory region.
+    ///
+    /// \returns True if the double-ended contiguous container <c>[storage_beg,
+    /// container_beg, container_end, end)</c> is properly poisoned - only
+    /// [container_beg; container_end) is addressable.
+    pub fn __sanitizer_verify_double_ended_contiguous_container(
+        storage_beg: *const c_void,
+        container_beg: *const c_void,
+        container_end: *const c_void,
+        storage_end: *const c_void,
+    ) -> c_int;
+    /// Similar to <c>__sanitizer_verify_double_ended_contiguous_container()</c>
+    /// but returns the address of the first improperly poisoned byte.
+    ///
+    /// Returns NULL if the area is poisoned properly.
+    ///
+    /// \param storage_beg Beginning of memory region.
+    /// \param container_beg Beginning of used region.
+    /// \param container_end End of used region.
+    /// \param storage_end End of memory region.
+    ///
+    /// \returns The bad address or NULL.
+    pub fn __sanitizer_double_ended_contiguous_container_find_bad_address(
+        storage_beg: *const c_void,
+        container_beg: *const c_void,
+        container_end: *const c_void,
+        storage_end: *const c_void,
+    ) -> *const c_void;
+}
+
+#[cfg(not(sanitize_address))]
+pub use self::noop::*;
+
+/// No-op fallbacks for the AddressSanitizer interface, used when the crate is
+/// built without AddressSanitizer enabled.
+#[cfg(not(sanitize_address))]
+#[allow(clippy::missing_safety_doc)]
+mod noop {
+    use super::*;
+    use std::ptr::null_mut;
+
+    pub unsafe fn __asan_poison_memory_region(_addr: *const c_void, _size: usize) {}
+    pub unsafe fn __asan_unpoison_memory_region(_addr: *const c_void, _size: usize) {}
+    pub unsafe fn __asan_address_is_poisoned(_addr: *const c_void) -> c_int {
+        0
+    }
+    pub unsafe fn __asan_region_is_poisoned(_beg: *mut c_void, _size: usize) -> *mut c_void {
+        null_mut()
+    }
+    pub unsafe fn __asan_describe_address(_addr: *mut c_void) {}
+    pub unsafe fn __asan_report_present() -> c_int {
+        0
+    }
+    pub unsafe fn __asan_get_report_pc() -> *mut c_void {
+        null_mut()
+    }
+    pub unsafe fn __asan_get_report_bp() -> *mut c_void {
+        null_mut()
+    }
+    pub unsafe fn __asan_get_report_sp() -> *mut c_void {
+        null_mut()
+    }
+    pub unsafe fn __asan_get_report_address() -> *mut c_void {
+        null_mut()
+    }
+    pub unsafe fn __asan_get_report_access_type() -> c_int {
+        0
+    }
+    pub unsafe fn __asan_get_report_access_size() -> usize {
+        0
+    }
+    pub unsafe fn __asan_get_report_description() -> *const c_char {
+        std::ptr::null()
+    }
+    pub unsafe fn __asan_locate_address(
+        _addr: *mut c_void,
+        _name: *mut c_char,
+        _name_size: usize,
+        _region_address: *mut *mut c_void,
+        _region_size: *mut usize,
+    ) -> *const c_char {
+        std::ptr::null()
+    }
+    pub unsafe fn __asan_get_alloc_stack(
+        _addr: *mut c_void,
+        _trace: *mut *mut c_void,
+        _size: usize,
+        _thread_id: *mut c_int,
+    ) -> usize {
+        0
+    }
+    pub unsafe fn __asan_get_free_stack(
+        _addr: *mut c_void,
+        _trace: *mut *mut c_void,
+        _size: usize,
+        _thread_id: *mut c_int,
+    ) -> usize {
+        0
+    }
+    pub unsafe fn __asan_get_shadow_mapping(_shadow_scale: *mut usize, _shadow_offset: *mut usize) {
+    }
+    pub unsafe fn __asan_report_error(
+        _pc: *mut c_void,
+        _bp: *mut c_void,
+        _sp: *mut c_void,
+        _addr: *mut c_void,
+        _is_write: c_int,
+        _access_size: usize,
+    ) {
+    }
+    pub unsafe fn __asan_set_death_callback(_callback: Option<unsafe extern "C" fn()>) {}
+    pub unsafe fn __asan_set_error_report_callback(
+        _callback: Option<unsafe extern "C" fn(arg1: *const c_char)>,
+    ) {
+    }
+    pub unsafe fn __asan_on_error() {}
+    pub unsafe fn __asan_print_accumulated_stats() {}
+    pub unsafe fn __asan_default_options() -> *const c_char {
+        std::ptr::null()
+    }
+    pub unsafe fn __asan_get_current_fake_stack() -> *mut c_void {
+        null_mut()
+    }
+    pub unsafe fn __asan_addr_is_in_fake_stack(
+        _fake_stack: *mut c_void,
+        _addr: *mut c_void,
+        _beg: *mut *mut c_void,
+        _end: *mut *mut c_void,
+    ) -> *mut c_void {
+        null_mut()
+    }
+    pub unsafe fn __asan_handle_no_return() {}
+    pub unsafe fn __asan_update_allocation_context(_addr: *mut c_void) -> c_int {
+        0
+    }
+    pub unsafe fn __sanitizer_annotate_contiguous_container(
+        _beg: *const c_void,
+        _end: *const c_void,
+        _old_mid: *const c_void,
+        _new_mid: *const c_void,
+    ) {
+    }
+    pub unsafe fn __sanitizer_verify_contiguous_container(
+        _beg: *const c_void,
+        _mid: *const c_void,
+        _end: *const c_void,
+    ) -> c_int {
+        1
+    }
+    pub unsafe fn __sanitizer_contiguous_container_find_bad_address(
+        _beg: *const c_void,
+        _mid: *const c_void,
+        _end: *const c_void,
+    ) -> *const c_void {
+        std::ptr::null()
+    }
+    pub unsafe fn __sanitizer_annotate_double_ended_contiguous_container(
+        _storage_beg: *const c_void,
+        _storage_end: *const c_void,
+        _old_container_beg: *const c_void,
+        _old_container_end: *const c_void,
+        _new_container_beg: *const c_void,
+        _new_container_end: *const c_void,
+    ) {
+    }
+    pub unsafe fn __sanitizer_verify_double_ended_contiguous_container(
+        _storage_beg: *const c_void,
+        _container_beg: *const c_void,
+        _container_end: *const c_void,
+        _storage_end: *const c_void,
+    ) -> c_int {
+        1
+    }
+    pub unsafe fn __sanitizer_double_ended_contiguous_container_find_bad_address(
+        _storage_beg: *const c_void,
+        _container_beg: *const c_void,
+        _container_end: *const c_void,
+        _storage_end: *const c_void,
+    ) -> *const c_void {
+        std::ptr::null()
+    }
 }
@@ -0,0 +1,164 @@
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+
+/// Arguments for __sanitizer_sandbox_on_notify() below.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct __sanitizer_sandbox_arguments {
+    /// Enable sandbox support in sanitizer coverage.
+    pub coverage_sandboxed: c_int,
+    /// File descriptor to write coverage data to. If -1 is passed, a file will
+    /// be pre-opened by __sanitizer_sandbox_on_notify(). This field has no
+    /// effect if coverage_sandboxed == 0.
+    pub coverage_fd: isize,
+    /// If non-zero, split the coverage data into well-formed blocks. This is
+    /// useful when coverage_fd is a socket descriptor. Each block will contain
+    /// a header, allowing data from multiple processes to be sent over the same
+    /// socket.
+    pub coverage_max_block_size: c_uint,
+}
+
+#[cfg(any(
+    sanitize_address,
+    sanitize_dataflow,
+    sanitize_leak,
+    sanitize_memory,
+    sanitize_thread
+))]
+extern "C" {
+    /// Tell the tools to write their reports to "path.<pid>" instead of stderr.
+    pub fn __sanitizer_set_report_path(path: *const c_char);
+    /// Tell the tools to write their reports to the provided file descriptor
+    /// (casted to void *).
+    pub fn __sanitizer_set_report_fd(fd: *mut c_void);
+    /// Get the current full report file path, if a path was specified by
+    /// an earlier call to __sanitizer_set_report_path. Returns null otherwise.
+    pub fn __sanitizer_get_report_path() -> *const c_char;
+    /// Notify the tools that the sandbox is going to be turned on. The reserved
+    /// parameter will be used in the future to hold a structure with functions
+    /// that the tools may call to bypass the sandbox.
+    pub fn __sanitizer_sandbox_on_notify(args: *mut __sanitizer_sandbox_arguments);
+    /// Print the stack trace leading to this call. Useful for debugging user code.
+    pub fn __sanitizer_print_stack_trace();
+    /// Sets the callback to be called immediately before death on error.
+    ///
+    /// Passing 0 will unset the callback.
+    ///
+    /// \param callback User-provided callback.
+    pub fn __sanitizer_set_death_callback(callback: Option<unsafe extern "C" fn()>);
+    /// Returns 1 on the first call, then returns 0 thereafter.  Called by the tool
+    /// to ensure only one report is printed when multiple errors occur
+    /// simultaneously.
+    pub fn __sanitizer_acquire_crash_state() -> c_int;
+    /// Gets the path of the module that contains the given PC and the offset of
+    /// the PC in the module.
+    ///
+    /// Returns 1 if the PC belongs to a known module; 0 otherwise.
+    ///
+    /// \param pc PC to look up.
+    /// \param[out] module_path Buffer for the path of the module.
+    /// \param module_path_len Size of the buffer for the path of the module.
+    /// \param[out] pc_offset Offset of the PC in the module.
+    pub fn __sanitizer_get_module_and_offset_for_pc(
+        pc: *mut c_void,
+        module_path: *mut c_char,
+        module_path_len: usize,
+        pc_offset: *mut *mut c_void,
+    ) -> c_int;
+    /// Symbolizes the given PC (program counter) according to the given format
+    /// and writes the result, one null-terminated string per frame (including
+    /// inlined frames) followed by an empty string, to the given buffer.
+    ///
+    /// \param pc PC to symbolize (the runtime symbolizes the instruction before
+    /// it, i.e., <c>pc</c> is expected to be a return address).
+    /// \param fmt Format of each frame (e.g., "%f %s:%l:%c" for the function
+    /// name, source file, line and column).
+    /// \param[out] out_buf Buffer for the symbolized frames.
+    /// \param out_buf_size Size of the buffer for the symbolized frames.
+    pub fn __sanitizer_symbolize_pc(
+        pc: *mut c_void,
+        fmt: *const c_char,
+        out_buf: *mut c_char,
+        out_buf_size: usize,
+    );
+    /// Symbolizes the global variable that contains the given address according
+    /// to the given format ("%g" for the name, "%s" for the source file and "%l"
+    /// for the line) and writes the result to the given buffer, or an empty
+    /// string if the address does not belong to a global variable.
+    ///
+    /// \param data_ptr Address to symbolize.
+    /// \param fmt Format of the global variable.
+    /// \param[out] out_buf Buffer for the symbolized global variable.
+    /// \param out_buf_size Size of the buffer for the symbolized global variable.
+    pub fn __sanitizer_symbolize_global(
+        data_ptr: *mut c_void,
+        fmt: *const c_char,
+        out_buf: *mut c_char,
+        out_buf_size: usize,
+    );
+}
+
+#[cfg(not(any(
+    sanitize_address,
+    sanitize_dataflow,
+    sanitize_leak,
+    sanitize_memory,
+    sanitize_thread
+)))]
+pub use self::noop::*;
+
+/// No-op fallbacks for the common sanitizer interface, used when the crate is
+/// built without any sanitizer enabled.
+#[cfg(not(any(
+    sanitize_address,
+    sanitize_dataflow,
+    sanitize_leak,
+    sanitize_memory,
+    sanitize_thread
+)))]
+#[allow(clippy::missing_safety_doc)]
+mod noop {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static CRASH_STATE_ACQUIRED: AtomicBool = AtomicBool::new(false);
+
+    pub unsafe fn __sanitizer_set_report_path(_path: *const c_char) {}
+    pub unsafe fn __sanitizer_set_report_fd(_fd: *mut c_void) {}
+    pub unsafe fn __sanitizer_get_report_path() -> *const c_char {
+        std::ptr::null()
+    }
+    pub unsafe fn __sanitizer_sandbox_on_notify(_args: *mut __sanitizer_sandbox_arguments) {}
+    pub unsafe fn __sanitizer_print_stack_trace() {}
+    pub unsafe fn __sanitizer_set_death_callback(_callback: Option<unsafe extern "C" fn()>) {}
+    pub unsafe fn __sanitizer_acquire_crash_state() -> c_int {
+        !CRASH_STATE_ACQUIRED.swap(true, Ordering::AcqRel) as c_int
+    }
+    pub unsafe fn __sanitizer_get_module_and_offset_for_pc(
+        _pc: *mut c_void,
+        _module_path: *mut c_char,
+        _module_path_len: usize,
+        _pc_offset: *mut *mut c_void,
+    ) -> c_int {
+        0
+    }
+    pub unsafe fn __sanitizer_symbolize_pc(
+        _pc: *mut c_void,
+        _fmt: *const c_char,
+        out_buf: *mut c_char,
+        out_buf_size: usize,
+    ) {
+        if out_buf_size > 0 {
+            *out_buf = 0;
+        }
+    }
+    pub unsafe fn __sanitizer_symbolize_global(
+        _data_ptr: *mut c_void,
+        _fmt: *const c_char,
+        out_buf: *mut c_char,
+        out_buf_size: usize,
+    ) {
+        if out_buf_size > 0 {
+            *out_buf = 0;
+        }
+    }
+}
@@ -1,8 +1,5 @@
 #![allow(non_camel_case_types)]
-/// FFI bindings for the DataFlowSanitizer interface.
-///
-/// For more information about DataFlowSanitizer, see
-/// https://clang.llvm.org/docs/DataFlowSanitizer.html.
+
 use std::option::Option;
 use std::os::raw::{c_char, c_int, c_long, c_uint, c_void};
 
@@ -25,6 +22,7 @@ pub type dfsan_reaches_function_callback_t = Option<
         function: *const c_char,
     ),
 >;
+#[cfg(sanitize_dataflow)]
 extern "C" {
     /// Computes the union of \c l1 and \c l2, resulting in a union label.
     pub fn dfsan_union(l1: dfsan_label, l2: dfsan_label) -> dfsan_label;
@@ -182,3 +180,110 @@ extern "C" {
     /// * 2: track origins at memory load and store operations.
     pub fn dfsan_get_track_origins() -> c_int;
 }
+
+#[cfg(not(sanitize_dataflow))]
+pub use self::noop::*;
+
+/// No-op fallbacks for the DataFlowSanitizer interface, used when the crate is
+/// built without DataFlowSanitizer enabled.
+///
+/// Label arithmetic (i.e., `dfsan_union` and `dfsan_has_label`) does not depend
+/// on the runtime and is implemented as in the runtime; everything else behaves
+/// as if no data were labeled.
+#[cfg(not(sanitize_dataflow))]
+#[allow(clippy::missing_safety_doc)]
+mod noop {
+    use super::*;
+
+    pub unsafe fn dfsan_union(l1: dfsan_label, l2: dfsan_label) -> dfsan_label {
+        l1 | l2
+    }
+    pub unsafe fn dfsan_set_label(_label: dfsan_label, _addr: *mut c_void, _size: usize) {}
+    pub unsafe fn dfsan_add_label(_label: dfsan_label, _addr: *mut c_void, _size: usize) {}
+    pub unsafe fn dfsan_get_label(_data: c_long) -> dfsan_label {
+        0
+    }
+    pub unsafe fn dfsan_get_origin(_data: c_long) -> dfsan_origin {
+        0
+    }
+    pub unsafe fn dfsan_read_label(_addr: *const c_void, _size: usize) -> dfsan_label {
+        0
+    }
+    pub unsafe fn dfsan_read_origin_of_first_taint(
+        _addr: *const c_void,
+        _size: usize,
+    ) -> dfsan_origin {
+        0
+    }
+    pub unsafe fn dfsan_has_label(label: dfsan_label, elem: dfsan_label) -> c_int {
+        (label & elem == elem) as c_int
+    }
+    pub unsafe fn dfsan_flush() {}
+    pub unsafe fn dfsan_set_write_callback(_labeled_write_callback: dfsan_write_callback_t) {}
+    pub unsafe fn dfsan_set_conditional_callback(_callback: dfsan_conditional_callback_t) {}
+    pub unsafe fn dfsan_get_labels_in_signal_conditional() -> dfsan_label {
+        0
+    }
+    pub unsafe fn dfsan_set_reaches_function_callback(
+        _callback: dfsan_reaches_function_callback_t,
+    ) {
+    }
+    pub unsafe fn dfsan_get_labels_in_signal_reaches_function() -> dfsan_label {
+        0
+    }
+    pub unsafe fn dfsan_weak_hook_memcmp(
+        _caller_pc: *mut c_void,
+        _s1: *const c_void,
+        _s2: *const c_void,
+        _n: usize,
+        _s1_label: dfsan_label,
+        _s2_label: dfsan_label,
+        _n_label: dfsan_label,
+    ) {
+    }
+    pub unsafe fn dfsan_weak_hook_strncmp(
+        _caller_pc: *mut c_void,
+        _s1: *const c_char,
+        _s2: *const c_char,
+        _n: usize,
+        _s1_label: dfsan_label,
+        _s2_label: dfsan_label,
+        _n_label: dfsan_label,
+    ) {
+    }
+    pub unsafe fn dfsan_print_origin_trace(_addr: *const c_void, _description: *const c_char) {}
+    pub unsafe fn dfsan_print_origin_id_trace(_origin: dfsan_origin) {}
+    pub unsafe fn dfsan_sprint_origin_trace(
+        _addr: *const c_void,
+        _description: *const c_char,
+        out_buf: *mut c_char,
+        out_buf_size: usize,
+    ) -> usize {
+        if !out_buf.is_null() && out_buf_size > 0 {
+            *out_buf = 0;
+        }
+        0
+    }
+    pub unsafe fn dfsan_sprint_origin_id_trace(
+        _origin: dfsan_origin,
+        out_buf: *mut c_char,
+        out_buf_size: usize,
+    ) -> usize {
+        if !out_buf.is_null() && out_buf_size > 0 {
+            *out_buf = 0;
+        }
+        0
+    }
+    pub unsafe fn dfsan_sprint_stack_trace(out_buf: *mut c_char, out_buf_size: usize) -> usize {
+        if !out_buf.is_null() && out_buf_size > 0 {
+            *out_buf = 0;
+        }
+        0
+    }
+    pub unsafe fn dfsan_get_init_origin(_addr: *const c_void) -> dfsan_origin {
+        0
+    }
+    pub unsafe fn dfsan_get_track_origins() -> c_int {
+        0
+    }
+}
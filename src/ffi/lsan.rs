@@ -1,9 +1,6 @@
-/// FFI bindings for the LeakSanitizer interface.
-///
-/// For more information about LeakSanitizer, see
-/// https://clang.llvm.org/docs/LeakSanitizer.html.
 use std::os::raw::{c_int, c_void};
 
+#[cfg(any(sanitize_address, sanitize_leak))]
 extern "C" {
     /// Allocations made between calls to __lsan_disable() and __lsan_enable() will
     /// be treated as non-leaks. Disable/enable pairs may be nested.
@@ -16,12 +13,12 @@ extern "C" {
     /// mapped memory.
     /// Points of note:
     /// - __lsan_unregister_root_region() must be called with the same pointer and
-    /// size that have earlier been passed to __lsan_register_root_region()
+    ///   size that have earlier been passed to __lsan_register_root_region()
     /// - LSan will skip any inaccessible memory when scanning a root region. E.g.,
-    /// if you map memory within a larger region that you have mprotect'ed, you can
-    /// register the entire large region.
+    ///   if you map memory within a larger region that you have mprotect'ed, you can
+    ///   register the entire large region.
     /// - the implementation is not optimized for performance. This interface is
-    /// intended to be used for a small number of relatively static regions.
+    ///   intended to be used for a small number of relatively static regions.
     pub fn __lsan_register_root_region(p: *const c_void, size: usize);
     pub fn __lsan_unregister_root_region(p: *const c_void, size: usize);
     /// Check for leaks now. This function behaves identically to the default
@@ -42,3 +39,24 @@ extern "C" {
     /// affected by them.
     pub fn __lsan_do_recoverable_leak_check() -> c_int;
 }
+
+#[cfg(not(any(sanitize_address, sanitize_leak)))]
+pub use self::noop::*;
+
+/// No-op fallbacks for the LeakSanitizer interface, used when the crate is
+/// built without LeakSanitizer (or AddressSanitizer) enabled.
+#[cfg(not(any(sanitize_address, sanitize_leak)))]
+#[allow(clippy::missing_safety_doc)]
+mod noop {
+    use super::*;
+
+    pub unsafe fn __lsan_disable() {}
+    pub unsafe fn __lsan_enable() {}
+    pub unsafe fn __lsan_ignore_object(_p: *const c_void) {}
+    pub unsafe fn __lsan_register_root_region(_p: *const c_void, _size: usize) {}
+    pub unsafe fn __lsan_unregister_root_region(_p: *const c_void, _size: usize) {}
+    pub unsafe fn __lsan_do_leak_check() {}
+    pub unsafe fn __lsan_do_recoverable_leak_check() -> c_int {
+        0
+    }
+}
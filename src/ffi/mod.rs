@@ -1,7 +1,37 @@
-/// FFI bindings for the [sanitizers](https://github.com/google/sanitizers)
-/// interfaces.
+/// FFI bindings for the allocator interface (i.e., the interface of the
+/// allocators of the sanitizer runtimes).
+///
+/// For more information about the allocator interface, see
+/// <https://github.com/llvm/llvm-project/blob/main/compiler-rt/include/sanitizer/allocator_interface.h>.
+pub mod allocator;
+/// FFI bindings for the AddressSanitizer interface.
+///
+/// For more information about AddressSanitizer, see
+/// <https://clang.llvm.org/docs/AddressSanitizer.html>.
 pub mod asan;
+/// FFI bindings for the common sanitizer interface (i.e., the interface shared
+/// by all the sanitizer runtimes).
+///
+/// For more information about the common sanitizer interface, see
+/// <https://github.com/llvm/llvm-project/blob/main/compiler-rt/include/sanitizer/common_interface_defs.h>.
+pub mod common;
+/// FFI bindings for the DataFlowSanitizer interface.
+///
+/// For more information about DataFlowSanitizer, see
+/// <https://clang.llvm.org/docs/DataFlowSanitizer.html>.
 pub mod dfsan;
+/// FFI bindings for the LeakSanitizer interface.
+///
+/// For more information about LeakSanitizer, see
+/// <https://clang.llvm.org/docs/LeakSanitizer.html>.
 pub mod lsan;
+/// FFI bindings for the MemorySanitizer interface.
+///
+/// For more information about MemorySanitizer, see
+/// <https://clang.llvm.org/docs/MemorySanitizer.html>.
 pub mod msan;
+/// FFI bindings for the ThreadSanitizer interface.
+///
+/// For more information about ThreadSanitizer, see
+/// <https://clang.llvm.org/docs/ThreadSanitizer.html>.
 pub mod tsan;
@@ -1,10 +1,7 @@
-/// FFI bindings for the MemorySanitizer interface.
-///
-/// For more information about MemorySanitizer, see
-/// https://clang.llvm.org/docs/MemorySanitizer.html.
 use std::option::Option;
 use std::os::raw::{c_char, c_int, c_void};
 
+#[cfg(sanitize_memory)]
 extern "C" {
     /// Set raw origin for the memory range.
     pub fn __msan_set_origin(a: *const c_void, size: usize, origin: u32);
@@ -80,3 +77,61 @@ extern "C" {
     pub fn __msan_start_switch_fiber(bottom: *const c_void, size: usize);
     pub fn __msan_finish_switch_fiber(bottom_old: *mut *const c_void, size_old: *mut usize);
 }
+
+#[cfg(not(sanitize_memory))]
+pub use self::noop::*;
+
+/// No-op fallbacks for the MemorySanitizer interface, used when the crate is
+/// built without MemorySanitizer enabled.
+#[cfg(not(sanitize_memory))]
+#[allow(clippy::missing_safety_doc)]
+mod noop {
+    use super::*;
+
+    pub unsafe fn __msan_set_origin(_a: *const c_void, _size: usize, _origin: u32) {}
+    pub unsafe fn __msan_get_origin(_a: *const c_void) -> u32 {
+        0
+    }
+    pub unsafe fn __msan_origin_is_descendant_or_same(this_id: u32, prev_id: u32) -> c_int {
+        (this_id == prev_id) as c_int
+    }
+    pub unsafe fn __msan_get_track_origins() -> c_int {
+        0
+    }
+    pub unsafe fn __msan_get_umr_origin() -> u32 {
+        0
+    }
+    pub unsafe fn __msan_unpoison(_a: *const c_void, _size: usize) {}
+    pub unsafe fn __msan_unpoison_string(_a: *const c_char) {}
+    pub unsafe fn __msan_unpoison_param(_n: usize) {}
+    pub unsafe fn __msan_poison(_a: *const c_void, _size: usize) {}
+    pub unsafe fn __msan_partial_poison(_data: *const c_void, _shadow: *mut c_void, _size: usize) {}
+    pub unsafe fn __msan_test_shadow(_x: *const c_void, _size: usize) -> isize {
+        -1
+    }
+    pub unsafe fn __msan_check_mem_is_initialized(_x: *const c_void, _size: usize) {}
+    pub unsafe fn __msan_set_expect_umr(_expect_umr: c_int) {}
+    pub unsafe fn __msan_set_keep_going(_keep_going: c_int) {}
+    pub unsafe fn __msan_print_shadow(_x: *const c_void, _size: usize) {}
+    pub unsafe fn __msan_dump_shadow(_x: *const c_void, _size: usize) {}
+    pub unsafe fn __msan_has_dynamic_component() -> c_int {
+        0
+    }
+    pub unsafe fn __msan_allocated_memory(_data: *const c_void, _size: usize) {}
+    pub unsafe fn __sanitizer_dtor_callback(_data: *const c_void, _size: usize) {}
+    pub unsafe fn __sanitizer_dtor_callback_fields(_data: *const c_void, _size: usize) {}
+    pub unsafe fn __sanitizer_dtor_callback_vptr(_data: *const c_void) {}
+    pub unsafe fn __msan_default_options() -> *const c_char {
+        std::ptr::null()
+    }
+    pub unsafe fn __msan_set_death_callback(_callback: Option<unsafe extern "C" fn()>) {}
+    pub unsafe fn __msan_copy_shadow(_dst: *const c_void, _src: *const c_void, _size: usize) {}
+    pub unsafe fn __msan_scoped_disable_interceptor_checks() {}
+    pub unsafe fn __msan_scoped_enable_interceptor_checks() {}
+    pub unsafe fn __msan_start_switch_fiber(_bottom: *const c_void, _size: usize) {}
+    pub unsafe fn __msan_finish_switch_fiber(
+        _bottom_old: *mut *const c_void,
+        _size_old: *mut usize,
+    ) {
+    }
+}
@@ -1,8 +1,4 @@
 #![allow(non_camel_case_types, non_upper_case_globals)]
-/// FFI bindings for the ThreadSanitizer interface.
-///
-/// For more information about ThreadSanitizer, see
-/// https://clang.llvm.org/docs/ThreadSanitizer.html.
 use std::os::raw::{c_char, c_int, c_uint, c_ulong, c_void};
 
 /// Annotations for custom mutexes.
@@ -52,6 +48,7 @@ pub const __tsan_mutex_try_read_lock_failed: c_uint = 56;
 /// Flags for __tsan_switch_to_fiber:
 /// Do not establish a happens-before relation between fibers
 pub const __tsan_switch_to_fiber_no_sync: c_uint = 1;
+#[cfg(sanitize_thread)]
 extern "C" {
     /// __tsan_release establishes a happens-before relation with a preceding
     /// __tsan_acquire on the same address.
@@ -115,6 +112,7 @@ extern "C" {
     ///   - __tsan_external_register_tag registers a 'tag' with the specified name,
     ///       which is later used in read/write annotations to denote the object type
     ///   - __tsan_external_assign_tag can optionally mark a heap object with a tag
+    #[allow(clippy::doc_overindented_list_items)]
     pub fn __tsan_external_register_tag(object_type: *const c_char) -> *mut c_void;
     pub fn __tsan_external_register_header(tag: *mut c_void, header: *const c_char);
     pub fn __tsan_external_assign_tag(addr: *mut c_void, tag: *mut c_void);
@@ -302,3 +300,153 @@ extern "C" {
     /// \returns An opaque pointer to the current report. Otherwise returns NULL.
     pub fn __tsan_get_current_report() -> *mut c_void;
 }
+
+#[cfg(not(sanitize_thread))]
+pub use self::noop::*;
+
+/// No-op fallbacks for the ThreadSanitizer interface, used when the crate is
+/// built without ThreadSanitizer enabled.
+#[cfg(not(sanitize_thread))]
+#[allow(clippy::missing_safety_doc, clippy::too_many_arguments)]
+mod noop {
+    use super::*;
+    use std::ptr::null_mut;
+
+    pub unsafe fn __tsan_acquire(_addr: *mut c_void) {}
+    pub unsafe fn __tsan_release(_addr: *mut c_void) {}
+    pub unsafe fn __tsan_mutex_create(_addr: *mut c_void, _flags: c_uint) {}
+    pub unsafe fn __tsan_mutex_destroy(_addr: *mut c_void, _flags: c_uint) {}
+    pub unsafe fn __tsan_mutex_pre_lock(_addr: *mut c_void, _flags: c_uint) {}
+    pub unsafe fn __tsan_mutex_post_lock(_addr: *mut c_void, _flags: c_uint, _recursion: c_int) {}
+    pub unsafe fn __tsan_mutex_pre_unlock(_addr: *mut c_void, _flags: c_uint) -> c_int {
+        0
+    }
+    pub unsafe fn __tsan_mutex_post_unlock(_addr: *mut c_void, _flags: c_uint) {}
+    pub unsafe fn __tsan_mutex_pre_signal(_addr: *mut c_void, _flags: c_uint) {}
+    pub unsafe fn __tsan_mutex_post_signal(_addr: *mut c_void, _flags: c_uint) {}
+    pub unsafe fn __tsan_mutex_pre_divert(_addr: *mut c_void, _flags: c_uint) {}
+    pub unsafe fn __tsan_mutex_post_divert(_addr: *mut c_void, _flags: c_uint) {}
+    pub unsafe fn __tsan_check_no_mutexes_held() {}
+    pub unsafe fn __tsan_external_register_tag(_object_type: *const c_char) -> *mut c_void {
+        null_mut()
+    }
+    pub unsafe fn __tsan_external_register_header(_tag: *mut c_void, _header: *const c_char) {}
+    pub unsafe fn __tsan_external_assign_tag(_addr: *mut c_void, _tag: *mut c_void) {}
+    pub unsafe fn __tsan_external_read(
+        _addr: *mut c_void,
+        _caller_pc: *mut c_void,
+        _tag: *mut c_void,
+    ) {
+    }
+    pub unsafe fn __tsan_external_write(
+        _addr: *mut c_void,
+        _caller_pc: *mut c_void,
+        _tag: *mut c_void,
+    ) {
+    }
+    pub unsafe fn __tsan_get_current_fiber() -> *mut c_void {
+        null_mut()
+    }
+    pub unsafe fn __tsan_create_fiber(_flags: c_uint) -> *mut c_void {
+        null_mut()
+    }
+    pub unsafe fn __tsan_destroy_fiber(_fiber: *mut c_void) {}
+    pub unsafe fn __tsan_switch_to_fiber(_fiber: *mut c_void, _flags: c_uint) {}
+    pub unsafe fn __tsan_set_fiber_name(_fiber: *mut c_void, _name: *const c_char) {}
+    pub unsafe fn __tsan_on_initialize() {}
+    pub unsafe fn __tsan_on_finalize(failed: c_int) -> c_int {
+        failed
+    }
+    pub unsafe fn __tsan_flush_memory() {}
+    pub unsafe fn __tsan_default_options() -> *const c_char {
+        std::ptr::null()
+    }
+    pub unsafe fn __tsan_default_suppressions() -> *const c_char {
+        std::ptr::null()
+    }
+    pub unsafe fn __tsan_get_report_data(
+        _report: *mut c_void,
+        _description: *mut *const c_char,
+        _count: *mut c_int,
+        _stack_count: *mut c_int,
+        _mop_count: *mut c_int,
+        _loc_count: *mut c_int,
+        _mutex_count: *mut c_int,
+        _thread_count: *mut c_int,
+        _unique_tid_count: *mut c_int,
+        _sleep_trace: *mut *mut c_void,
+        _trace_size: c_ulong,
+    ) -> c_int {
+        0
+    }
+    pub unsafe fn __tsan_get_report_stack(
+        _report: *mut c_void,
+        _idx: c_ulong,
+        _trace: *mut *mut c_void,
+        _trace_size: c_ulong,
+    ) -> c_int {
+        0
+    }
+    pub unsafe fn __tsan_get_report_mop(
+        _report: *mut c_void,
+        _idx: c_ulong,
+        _tid: *mut c_int,
+        _addr: *mut *mut c_void,
+        _size: *mut c_int,
+        _write: *mut c_int,
+        _atomic: *mut c_int,
+        _trace: *mut *mut c_void,
+        _trace_size: c_ulong,
+    ) -> c_int {
+        0
+    }
+    pub unsafe fn __tsan_get_report_loc(
+        _report: *mut c_void,
+        _idx: c_ulong,
+        _type_: *mut *const c_char,
+        _addr: *mut *mut c_void,
+        _start: *mut *mut c_void,
+        _size: *mut c_ulong,
+        _tid: *mut c_int,
+        _fd: *mut c_int,
+        _suppressable: *mut c_int,
+        _trace: *mut *mut c_void,
+        _trace_size: c_ulong,
+    ) -> c_int {
+        0
+    }
+    pub unsafe fn __tsan_get_report_mutex(
+        _report: *mut c_void,
+        _idx: c_ulong,
+        _mutex_id: *mut u64,
+        _addr: *mut *mut c_void,
+        _destroyed: *mut c_int,
+        _trace: *mut *mut c_void,
+        _trace_size: c_ulong,
+    ) -> c_int {
+        0
+    }
+    pub unsafe fn __tsan_get_report_thread(
+        _report: *mut c_void,
+        _idx: c_ulong,
+        _tid: *mut c_int,
+        _os_id: *mut u64,
+        _running: *mut c_int,
+        _name: *mut *const c_char,
+        _parent_tid: *mut c_int,
+        _trace: *mut *mut c_void,
+        _trace_size: c_ulong,
+    ) -> c_int {
+        0
+    }
+    pub unsafe fn __tsan_get_report_unique_tid(
+        _report: *mut c_void,
+        _idx: c_ulong,
+        _tid: *mut c_int,
+    ) -> c_int {
+        0
+    }
+    pub unsafe fn __tsan_get_current_report() -> *mut c_void {
+        null_mut()
+    }
+}
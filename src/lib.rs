@@ -1,7 +1,53 @@
-/// [sanitizers](https://github.com/google/sanitizers) interfaces.
+//! [sanitizers](https://github.com/google/sanitizers) interfaces.
+
+/// Allocator interface (i.e., the interface of the allocators of the sanitizer
+/// runtimes), which works with whichever sanitizer is enabled.
+///
+/// For more information about the allocator interface, see
+/// <https://github.com/llvm/llvm-project/blob/main/compiler-rt/include/sanitizer/allocator_interface.h>.
+pub mod allocator;
+/// AddressSanitizer interface.
+///
+/// For more information about AddressSanitizer, see
+/// <https://clang.llvm.org/docs/AddressSanitizer.html>.
 pub mod asan;
+/// Common sanitizer interface (i.e., the interface shared by all the sanitizer
+/// runtimes), which works with whichever sanitizer is enabled.
+///
+/// For more information about the common sanitizer interface, see
+/// <https://github.com/llvm/llvm-project/blob/main/compiler-rt/include/sanitizer/common_interface_defs.h>.
+pub mod common;
+/// DataFlowSanitizer interface.
+///
+/// For more information about DataFlowSanitizer, see
+/// <https://clang.llvm.org/docs/DataFlowSanitizer.html>.
 pub mod dfsan;
+/// FFI bindings for the [sanitizers](https://github.com/google/sanitizers)
+/// interfaces.
 pub mod ffi;
+/// LeakSanitizer interface.
+///
+/// For more information about LeakSanitizer, see
+/// <https://clang.llvm.org/docs/LeakSanitizer.html>.
 pub mod lsan;
+/// MemorySanitizer interface.
+///
+/// For more information about MemorySanitizer, see
+/// <https://clang.llvm.org/docs/MemorySanitizer.html>.
 pub mod msan;
+/// Runtime options for the sanitizers.
+///
+/// For more information about the runtime options, see
+/// <https://github.com/google/sanitizers/wiki/SanitizerCommonFlags>.
+pub mod options;
+/// Suppressions for the sanitizers.
+///
+/// For more information about the suppressions, see
+/// <https://github.com/google/sanitizers/wiki/ThreadSanitizerSuppressions> and
+/// <https://github.com/google/sanitizers/wiki/AddressSanitizerLeakSanitizer#suppressions>.
+pub mod suppressions;
+/// ThreadSanitizer interface.
+///
+/// For more information about ThreadSanitizer, see
+/// <https://clang.llvm.org/docs/ThreadSanitizer.html>.
 pub mod tsan;
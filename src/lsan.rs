@@ -1,9 +1,6 @@
-/// LeakSanitizer interface.
-///
-/// For more information about LeakSanitizer, see
-/// https://clang.llvm.org/docs/LeakSanitizer.html.
 use crate::ffi::lsan::*;
 
+use std::mem::size_of_val;
 use std::os::raw::c_void;
 
 /// Disables leak detection.
@@ -21,7 +18,11 @@ pub fn enable() {
 }
 
 /// The heap object into which p points will be treated as a non-leak.
-pub fn ignore_object(p: *const c_void) {
+///
+/// # Safety
+///
+/// `p` must be an address in the application memory.
+pub unsafe fn ignore_object(p: *const c_void) {
     unsafe {
         __lsan_ignore_object(p);
     }
@@ -29,19 +30,58 @@ pub fn ignore_object(p: *const c_void) {
 
 /// Memory regions registered through this interface will be treated as sources
 /// of live pointers during leak checking.
-pub fn register_root_region(p: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `p` must point to a memory region of at least `size` bytes that remains
+/// mapped until it is unregistered.
+pub unsafe fn register_root_region(p: *const c_void, size: usize) {
     unsafe {
         __lsan_register_root_region(p, size);
     }
 }
 
 /// Unregisters a root region previously registered.
-pub fn unregister_root_region(p: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `p` and `size` must be the same as previously passed to
+/// `register_root_region`.
+pub unsafe fn unregister_root_region(p: *const c_void, size: usize) {
     unsafe {
         __lsan_unregister_root_region(p, size);
     }
 }
 
+/// The heap object that contains `value` will be treated as a non-leak (e.g.,
+/// `&*boxed` ignores the allocation of a `Box`, but `&boxed` refers to the `Box`
+/// itself and not to its allocation).
+pub fn ignore_object_val<T: ?Sized>(value: &T) {
+    unsafe {
+        ignore_object(value as *const T as *const c_void);
+    }
+}
+
+/// The memory of `value` (e.g., a slice) will be treated as a source of live
+/// pointers during leak checking.
+///
+/// # Safety
+///
+/// The memory of `value` must remain mapped until it is unregistered, even
+/// after `value` is no longer borrowed.
+pub unsafe fn register_root_region_val<T: ?Sized>(value: &T) {
+    unsafe {
+        register_root_region(value as *const T as *const c_void, size_of_val(value));
+    }
+}
+
+/// Unregisters the memory of `value` previously registered.
+pub fn unregister_root_region_val<T: ?Sized>(value: &T) {
+    unsafe {
+        unregister_root_region(value as *const T as *const c_void, size_of_val(value));
+    }
+}
+
 /// Check for leaks now. This function behaves identically to the default
 /// end-of-process leak check.
 pub fn do_leak_check() {
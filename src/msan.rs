@@ -1,24 +1,47 @@
-/// MemorySanitizer interface.
-///
-/// For more information about MemorySanitizer, see
-/// https://clang.llvm.org/docs/MemorySanitizer.html.
+use crate::common;
 use crate::ffi::msan::*;
 
 use std::ffi::CStr;
+use std::mem::size_of_val;
 use std::os::raw::{c_char, c_int, c_void};
 
 /// Set raw origin for the memory range.
-pub fn set_origin(a: *const c_void, size: usize, origin: u32) {
+///
+/// # Safety
+///
+/// `a` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn set_origin(a: *const c_void, size: usize, origin: u32) {
     unsafe {
         __msan_set_origin(a, size, origin);
     }
 }
 
+/// Set raw origin for the memory of `value`.
+pub fn set_origin_val<T: ?Sized>(value: &T, origin: u32) {
+    unsafe {
+        set_origin(
+            value as *const T as *const c_void,
+            size_of_val(value),
+            origin,
+        );
+    }
+}
+
 /// Get raw origin for an address.
-pub fn get_origin(a: *const c_void) -> u32 {
+///
+/// # Safety
+///
+/// `a` must be an address in the application memory.
+pub unsafe fn get_origin(a: *const c_void) -> u32 {
     unsafe { __msan_get_origin(a) }
 }
 
+/// Get raw origin for the address of `value`.
+pub fn get_origin_val<T: ?Sized>(value: &T) -> u32 {
+    unsafe { get_origin(value as *const T as *const c_void) }
+}
+
 /// Test that this_id is a descendant of prev_id (or they are simply equal).
 pub fn origin_is_descendant_or_same(this_id: u32, prev_id: u32) -> bool {
     unsafe { __msan_origin_is_descendant_or_same(this_id, prev_id) != 0 }
@@ -35,15 +58,32 @@ pub fn get_umr_origin() -> u32 {
 }
 
 /// Make memory region fully initialized (without changing its contents).
-pub fn unpoison(a: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `a` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn unpoison(a: *const c_void, size: usize) {
     unsafe {
         __msan_unpoison(a, size);
     }
 }
 
+/// Make the memory of `value` (e.g., a slice or a `MaybeUninit<T>`) fully
+/// initialized (without changing its contents).
+pub fn unpoison_val<T: ?Sized>(value: &T) {
+    unsafe {
+        unpoison(value as *const T as *const c_void, size_of_val(value));
+    }
+}
+
 /// Make a null-terminated string fully initialized (without changing its
 /// contents).
-pub fn unpoison_string(a: *const c_char) {
+///
+/// # Safety
+///
+/// `a` must point to a valid null-terminated string.
+pub unsafe fn unpoison_string(a: *const c_char) {
     unsafe {
         __msan_unpoison_string(a);
     }
@@ -57,26 +97,64 @@ pub fn unpoison_param(n: usize) {
 }
 
 /// Make memory region fully uninitialized (without changing its contents).
-pub fn poison(a: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `a` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn poison(a: *const c_void, size: usize) {
     unsafe {
         __msan_poison(a, size);
     }
 }
 
+/// Make the memory of `value` (e.g., a slice or a `MaybeUninit<T>`) fully
+/// uninitialized (without changing its contents).
+pub fn poison_val<T: ?Sized>(value: &T) {
+    unsafe {
+        poison(value as *const T as *const c_void, size_of_val(value));
+    }
+}
+
 /// Returns the offset of the first (at least partially) poisoned byte in the
 /// memory range, or -1 if the whole range is good.
-pub fn test_shadow(x: *const c_void, size: usize) -> isize {
+///
+/// # Safety
+///
+/// `x` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn test_shadow(x: *const c_void, size: usize) -> isize {
     unsafe { __msan_test_shadow(x, size) }
 }
 
+/// Returns the offset of the first (at least partially) poisoned byte in the
+/// memory of `value`, or `None` if it is fully initialized.
+pub fn test_shadow_val<T: ?Sized>(value: &T) -> Option<usize> {
+    let offset = unsafe { test_shadow(value as *const T as *const c_void, size_of_val(value)) };
+    usize::try_from(offset).ok()
+}
+
 /// Checks that memory range is fully initialized, and reports an error if it is
 /// not.
-pub fn check_mem_is_initialized(x: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `x` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn check_mem_is_initialized(x: *const c_void, size: usize) {
     unsafe {
         __msan_check_mem_is_initialized(x, size);
     }
 }
 
+/// Checks that the memory of `value` is fully initialized, and reports an error
+/// if it is not.
+pub fn check_mem_is_initialized_val<T: ?Sized>(value: &T) {
+    unsafe {
+        check_mem_is_initialized(value as *const T as *const c_void, size_of_val(value));
+    }
+}
+
 /// For testing: Set expected uninitialized memory reads.
 pub fn set_expect_umr(expect_umr: c_int) {
     unsafe {
@@ -93,55 +171,118 @@ pub fn set_keep_going(keep_going: c_int) {
 
 /// Print shadow and origin for the memory range to stderr in a human-readable
 /// format.
-pub fn print_shadow(x: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `x` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn print_shadow(x: *const c_void, size: usize) {
     unsafe {
         __msan_print_shadow(x, size);
     }
 }
 
+/// Print shadow and origin for the memory of `value` to stderr in a
+/// human-readable format.
+pub fn print_shadow_val<T: ?Sized>(value: &T) {
+    unsafe {
+        print_shadow(value as *const T as *const c_void, size_of_val(value));
+    }
+}
+
 /// Print shadow for the memory range to stderr in a minimalistic human-readable
 /// format.
-pub fn dump_shadow(x: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `x` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn dump_shadow(x: *const c_void, size: usize) {
     unsafe {
         __msan_dump_shadow(x, size);
     }
 }
 
+/// Print shadow for the memory of `value` to stderr in a minimalistic
+/// human-readable format.
+pub fn dump_shadow_val<T: ?Sized>(value: &T) {
+    unsafe {
+        dump_shadow(value as *const T as *const c_void, size_of_val(value));
+    }
+}
+
 /// Returns true if running under a dynamic tool (DynamoRio-based).
 pub fn has_dynamic_component() -> bool {
     unsafe { __msan_has_dynamic_component() != 0 }
 }
 
 /// Tell MSan about newly allocated memory (ex.: custom allocator).
-pub fn allocated_memory(data: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `data` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn allocated_memory(data: *const c_void, size: usize) {
     unsafe {
         __msan_allocated_memory(data, size);
     }
 }
 
+/// Tell MSan about the newly allocated memory of `value`. Mark memory as
+/// uninitialized.
+pub fn allocated_memory_val<T: ?Sized>(value: &T) {
+    unsafe {
+        allocated_memory(value as *const T as *const c_void, size_of_val(value));
+    }
+}
+
 /// Tell MSan about newly destroyed memory. Mark memory as uninitialized.
-pub fn dtor_callback(data: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `data` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn dtor_callback(data: *const c_void, size: usize) {
     unsafe {
         __sanitizer_dtor_callback(data, size);
     }
 }
 
+/// Tell MSan about the newly destroyed memory of `value`. Mark memory as
+/// uninitialized.
+pub fn dtor_callback_val<T: ?Sized>(value: &T) {
+    unsafe {
+        dtor_callback(value as *const T as *const c_void, size_of_val(value));
+    }
+}
+
 /// Tell MSan about newly destroyed memory. Mark memory as uninitialized.
-pub fn dtor_callback_fields(data: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `data` must point to a memory region of at least `size` bytes in the
+/// application memory.
+pub unsafe fn dtor_callback_fields(data: *const c_void, size: usize) {
     unsafe {
         __sanitizer_dtor_callback_fields(data, size);
     }
 }
 
 /// Tell MSan about newly destroyed memory. Mark memory as uninitialized.
-pub fn dtor_callback_vptr(data: *const c_void) {
+///
+/// # Safety
+///
+/// `data` must point to an object with a vtable pointer in the application
+/// memory.
+pub unsafe fn dtor_callback_vptr(data: *const c_void) {
     unsafe {
         __sanitizer_dtor_callback_vptr(data);
     }
 }
 
-/// This function may be optionally provided by user and should return a string
-/// containing Msan runtime options.
+/// This function may be optionally provided by user (e.g., defined with
+/// `sanitizers::default_options!`) and should return a string containing Msan
+/// runtime options.
 pub fn default_options() -> String {
     unsafe {
         let options_ptr = __msan_default_options();
@@ -153,13 +294,63 @@ pub fn default_options() -> String {
     }
 }
 
+/// Sets a callback to be called right before MSan terminates the process
+/// after reporting an error (e.g., to flush buffers or write crash artifacts).
+///
+/// The runtime has a single death callback, so the callback replaces any
+/// callback previously set (including with `common::set_death_callback`).
+/// Panics in the callback are caught and ignored.
+pub fn set_death_callback<F>(callback: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    common::store_death_callback(Some(Box::new(callback)));
+    unsafe {
+        __msan_set_death_callback(Some(common::death_callback));
+    }
+}
+
+/// Clears the callback set with `set_death_callback`.
+pub fn clear_death_callback() {
+    unsafe {
+        __msan_set_death_callback(None);
+    }
+    common::store_death_callback(None);
+}
+
 /// Update shadow for the application copy of size bytes from src to dst.
-pub fn copy_shadow(dst: *const c_void, src: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `dst` and `src` must each point to a memory region of at least `size` bytes
+/// in the application memory.
+pub unsafe fn copy_shadow(dst: *const c_void, src: *const c_void, size: usize) {
     unsafe {
         __msan_copy_shadow(dst, src, size);
     }
 }
 
+/// Update shadow for the application copy of `src` to `dst`.
+///
+/// # Panics
+///
+/// Panics if `dst` and `src` have different sizes.
+pub fn copy_shadow_val<T: ?Sized>(dst: &mut T, src: &T) {
+    let size = size_of_val(src);
+    assert_eq!(
+        size_of_val(dst),
+        size,
+        "source and destination sizes differ"
+    );
+    unsafe {
+        copy_shadow(
+            dst as *mut T as *const c_void,
+            src as *const T as *const c_void,
+            size,
+        );
+    }
+}
+
 /// Disables uninitialized memory checks in interceptors.
 pub fn scoped_disable_interceptor_checks() {
     unsafe {
@@ -176,14 +367,22 @@ pub fn scoped_enable_interceptor_checks() {
 }
 
 /// Start a fiber switch.
-pub fn start_switch_fiber(bottom: *const c_void, size: usize) {
+///
+/// # Safety
+///
+/// `bottom` and `size` must describe the stack of the fiber being switched to.
+pub unsafe fn start_switch_fiber(bottom: *const c_void, size: usize) {
     unsafe {
         __msan_start_switch_fiber(bottom, size);
     }
 }
 
 /// Finish a fiber switch.
-pub fn finish_switch_fiber(bottom_old: *mut *const c_void, size_old: *mut usize) {
+///
+/// # Safety
+///
+/// `bottom_old` and `size_old` must each be either null or valid for writes.
+pub unsafe fn finish_switch_fiber(bottom_old: *mut *const c_void, size_old: *mut usize) {
     unsafe {
         __msan_finish_switch_fiber(bottom_old, size_old);
     }
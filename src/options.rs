@@ -0,0 +1,423 @@
+use std::fmt::{self, Display};
+use std::process::Command;
+use std::str;
+
+/// Defines the default runtime options for the sanitizers.
+///
+/// Emits the `__asan_default_options`, `__lsan_default_options`,
+/// `__msan_default_options`, and `__tsan_default_options` hooks (for the `asan`,
+/// `lsan`, `msan`, and `tsan` keys, respectively), which the sanitizer runtimes
+/// call during initialization. Options set with the environment variables
+/// (e.g., `ASAN_OPTIONS`) take precedence over the default options. The macro
+/// must be used at most once per sanitizer in the final binary.
+///
+/// The options are given either as string literals or as constant expressions
+/// of the options builders (e.g., [`AsanOptions`]), which are rendered at
+/// compile time. The hooks are called before the sanitizer runtimes are
+/// initialized, so the options cannot be computed at run time.
+///
+/// E.g.:
+///
+/// ```
+/// use sanitizers::options::TsanOptions;
+///
+/// sanitizers::default_options!(
+///     asan = "detect_leaks=1:halt_on_error=0",
+///     tsan = TsanOptions::new().history_size(7),
+/// );
+/// ```
+#[macro_export]
+macro_rules! default_options {
+    (@munch) => {};
+    (@munch $sanitizer:ident = $options:literal $(, $($rest:tt)*)?) => {
+        $crate::default_options!(@hook $sanitizer, literal $options);
+        $crate::default_options!(@munch $($($rest)*)?);
+    };
+    (@munch $sanitizer:ident = $options:expr $(, $($rest:tt)*)?) => {
+        $crate::default_options!(@hook $sanitizer, expr $options);
+        $crate::default_options!(@munch $($($rest)*)?);
+    };
+    (@hook asan, $($options:tt)+) => {
+        $crate::default_options!(@define __asan_default_options, $($options)+);
+    };
+    (@hook lsan, $($options:tt)+) => {
+        $crate::default_options!(@define __lsan_default_options, $($options)+);
+    };
+    (@hook msan, $($options:tt)+) => {
+        $crate::default_options!(@define __msan_default_options, $($options)+);
+    };
+    (@hook tsan, $($options:tt)+) => {
+        $crate::default_options!(@define __tsan_default_options, $($options)+);
+    };
+    (@hook $sanitizer:ident, $($options:tt)+) => {
+        ::std::compile_error!(::std::concat!(
+            "unsupported sanitizer: `",
+            ::std::stringify!($sanitizer),
+            "` (expected `asan`, `lsan`, `msan`, or `tsan`)"
+        ));
+    };
+    (@define $hook:ident, literal $options:literal) => {
+        const _: () = {
+            #[no_mangle]
+            pub extern "C" fn $hook() -> *const ::std::os::raw::c_char {
+                ::std::concat!($options, "\0").as_ptr() as *const ::std::os::raw::c_char
+            }
+        };
+    };
+    (@define $hook:ident, expr $options:expr) => {
+        const _: () = {
+            // The options are rendered at compile time because the hook is
+            // called before the sanitizer runtime is initialized (e.g., before
+            // the AddressSanitizer shadow memory is mapped).
+            static OPTIONS: [u8; $options.rendered_len() + 1] = $options.render();
+
+            #[no_mangle]
+            pub extern "C" fn $hook() -> *const ::std::os::raw::c_char {
+                OPTIONS.as_ptr() as *const ::std::os::raw::c_char
+            }
+        };
+    };
+    ($($options:tt)+) => {
+        $crate::default_options!(@munch $($options)+);
+    };
+}
+
+/// Writer that renders the options (and suppressions) in constant functions.
+///
+/// Only counts the bytes that do not fit in the buffer, so it can be used with
+/// an empty buffer to compute the length of the rendered options.
+pub(crate) struct OptionsWriter<'a> {
+    buffer: &'a mut [u8],
+    pub(crate) len: usize,
+}
+
+impl<'a> OptionsWriter<'a> {
+    pub(crate) const fn new(buffer: &'a mut [u8]) -> Self {
+        OptionsWriter { buffer, len: 0 }
+    }
+
+    pub(crate) const fn write_byte(&mut self, byte: u8) {
+        if self.len < self.buffer.len() {
+            self.buffer[self.len] = byte;
+        }
+        self.len += 1;
+    }
+
+    pub(crate) const fn write_str(&mut self, s: &str) {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            self.write_byte(bytes[i]);
+            i += 1;
+        }
+    }
+
+    const fn write_name(&mut self, name: &str) {
+        if self.len > 0 {
+            self.write_byte(b':');
+        }
+        self.write_str(name);
+        self.write_byte(b'=');
+    }
+
+    const fn write_bool(&mut self, name: &str, value: bool) {
+        self.write_name(name);
+        self.write_byte(if value { b'1' } else { b'0' });
+    }
+
+    const fn write_int(&mut self, name: &str, value: i128) {
+        self.write_name(name);
+        if value < 0 {
+            self.write_byte(b'-');
+        }
+        let value = value.unsigned_abs();
+        let mut divisor = 1;
+        while value / divisor >= 10 {
+            divisor *= 10;
+        }
+        while divisor > 0 {
+            self.write_byte(b'0' + (value / divisor % 10) as u8);
+            divisor /= 10;
+        }
+    }
+
+    /// Writes a string value, quoting it if it contains characters the
+    /// sanitizer runtimes use as option separators.
+    const fn write_quoted(&mut self, name: &str, value: &str) {
+        self.write_name(name);
+        let bytes = value.as_bytes();
+        let mut needs_quotes = false;
+        let mut has_double_quotes = false;
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b' ' | b'\t' | b'\n' | b'\r' | b':' | b',' => needs_quotes = true,
+                b'"' => has_double_quotes = true,
+                _ => {}
+            }
+            i += 1;
+        }
+        if needs_quotes {
+            let quote = if has_double_quotes { b'\'' } else { b'"' };
+            self.write_byte(quote);
+            self.write_str(value);
+            self.write_byte(quote);
+        } else {
+            self.write_str(value);
+        }
+    }
+}
+
+macro_rules! options {
+    (
+        $(#[$attr:meta])*
+        $name:ident, $env_var:literal {
+            $(
+                $(#[$field_attr:meta])*
+                $field:ident: $ty:ident,
+            )*
+        }
+    ) => {
+        $(#[$attr])*
+        ///
+        /// The options are rendered in the colon-separated form used by the
+        /// environment variables (e.g., `halt_on_error=0:log_path=/tmp/log`) by
+        /// the `Display` implementation, and can be embedded in the binary with
+        /// the `default_options!` macro or set for child processes with
+        #[doc = concat!("[`", stringify!($name), "::apply`].")]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+        pub struct $name<'a> {
+            $($field: Option<options!(@type $ty)>,)*
+            extra: Option<&'a str>,
+        }
+
+        impl<'a> $name<'a> {
+            /// The environment variable the sanitizer runtime reads the options
+            /// from.
+            pub const ENV_VAR: &'static str = $env_var;
+
+            /// Creates a new set of options with no options set (i.e., with the
+            /// sanitizer runtime defaults).
+            pub const fn new() -> Self {
+                $name {
+                    $($field: None,)*
+                    extra: None,
+                }
+            }
+
+            $(
+                options!(@setter $(#[$field_attr])* $field: $ty);
+            )*
+
+            /// Sets additional options that do not have dedicated setters, in
+            /// the colon-separated form (e.g., `fast_unwind_on_malloc=0`).
+            /// They are rendered after the other options.
+            pub const fn extra(mut self, options: &'a str) -> Self {
+                self.extra = Some(options);
+                self
+            }
+
+            /// Returns the length, in bytes, of the rendered options.
+            pub const fn rendered_len(&self) -> usize {
+                let mut writer = OptionsWriter::new(&mut []);
+                self.write(&mut writer);
+                writer.len
+            }
+
+            /// Renders the options into a null-terminated byte array.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `N` is not greater than the length of the rendered
+            /// options.
+            pub const fn render<const N: usize>(&self) -> [u8; N] {
+                let mut buffer = [0; N];
+                let mut writer = OptionsWriter::new(&mut buffer);
+                self.write(&mut writer);
+                assert!(writer.len < N, "buffer too small for the options");
+                buffer
+            }
+
+            /// Sets the options in the environment of the given child process.
+            pub fn apply<'c>(&self, command: &'c mut Command) -> &'c mut Command {
+                command.env(Self::ENV_VAR, self.to_string())
+            }
+
+            const fn write(&self, writer: &mut OptionsWriter<'_>) {
+                $(
+                    if let Some(value) = self.$field {
+                        options!(@write writer, $field: $ty, value);
+                    }
+                )*
+                if let Some(extra) = self.extra {
+                    if !extra.is_empty() {
+                        if writer.len > 0 {
+                            writer.write_byte(b':');
+                        }
+                        writer.write_str(extra);
+                    }
+                }
+            }
+        }
+
+        impl Display for $name<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let mut buffer = vec![0; self.rendered_len()];
+                self.write(&mut OptionsWriter::new(&mut buffer));
+                f.write_str(str::from_utf8(&buffer).map_err(|_| fmt::Error)?)
+            }
+        }
+    };
+    (@type str) => {
+        &'a str
+    };
+    (@type $ty:ident) => {
+        $ty
+    };
+    (@setter $(#[$attr:meta])* $field:ident: str) => {
+        $(#[$attr])*
+        pub const fn $field(mut self, value: &'a str) -> Self {
+            self.$field = Some(value);
+            self
+        }
+    };
+    (@setter $(#[$attr:meta])* $field:ident: $ty:ident) => {
+        $(#[$attr])*
+        pub const fn $field(mut self, value: $ty) -> Self {
+            self.$field = Some(value);
+            self
+        }
+    };
+    (@write $writer:ident, $field:ident: bool, $value:ident) => {
+        $writer.write_bool(stringify!($field), $value)
+    };
+    (@write $writer:ident, $field:ident: str, $value:ident) => {
+        $writer.write_quoted(stringify!($field), $value)
+    };
+    (@write $writer:ident, $field:ident: $ty:ident, $value:ident) => {
+        $writer.write_int(stringify!($field), $value as i128)
+    };
+}
+
+options! {
+    /// Runtime options for AddressSanitizer.
+    ///
+    /// For more information about the options, see
+    /// <https://github.com/google/sanitizers/wiki/AddressSanitizerFlags>.
+    AsanOptions, "ASAN_OPTIONS" {
+        /// Sets whether to exit after the first error is reported.
+        halt_on_error: bool,
+        /// Sets whether to enable LeakSanitizer.
+        detect_leaks: bool,
+        /// Sets the path prefix of the files the reports are written to.
+        log_path: str,
+        /// Sets whether to symbolize the stack traces in the reports.
+        symbolize: bool,
+        /// Sets whether to call `abort` instead of `_exit` after reporting an
+        /// error.
+        abort_on_error: bool,
+        /// Sets the exit code used after reporting an error.
+        exitcode: i32,
+        /// Sets the verbosity level.
+        verbosity: i32,
+        /// Sets whether to detect uses of stack variables after their function
+        /// returns.
+        detect_stack_use_after_return: bool,
+        /// Sets whether to detect overflows of annotated containers.
+        detect_container_overflow: bool,
+        /// Sets whether user poisoning of memory regions is enabled.
+        allow_user_poisoning: bool,
+        /// Sets the size, in megabytes, of the quarantine of freed memory.
+        quarantine_size_mb: u64,
+        /// Sets the maximum number of frames of the allocation and
+        /// deallocation stack traces.
+        malloc_context_size: u32,
+    }
+}
+
+options! {
+    /// Runtime options for LeakSanitizer.
+    ///
+    /// For more information about the options, see
+    /// <https://github.com/google/sanitizers/wiki/AddressSanitizerLeakSanitizer>.
+    LsanOptions, "LSAN_OPTIONS" {
+        /// Sets whether to enable leak detection.
+        detect_leaks: bool,
+        /// Sets the path prefix of the files the reports are written to.
+        log_path: str,
+        /// Sets whether to symbolize the stack traces in the reports.
+        symbolize: bool,
+        /// Sets the exit code used after reporting leaks.
+        exitcode: i32,
+        /// Sets the verbosity level.
+        verbosity: i32,
+        /// Sets whether to report the addresses of the leaked objects.
+        report_objects: bool,
+        /// Sets the maximum number of frames of the allocation stack traces.
+        malloc_context_size: u32,
+        /// Sets the path of the suppressions file.
+        suppressions: str,
+    }
+}
+
+options! {
+    /// Runtime options for MemorySanitizer.
+    MsanOptions, "MSAN_OPTIONS" {
+        /// Sets whether to exit after the first error is reported.
+        halt_on_error: bool,
+        /// Sets the path prefix of the files the reports are written to.
+        log_path: str,
+        /// Sets whether to symbolize the stack traces in the reports.
+        symbolize: bool,
+        /// Sets whether to call `abort` instead of `_exit` after reporting an
+        /// error.
+        abort_on_error: bool,
+        /// Sets the exit code used after reporting an error.
+        exitcode: i32,
+        /// Sets the verbosity level.
+        verbosity: i32,
+        /// Sets whether to poison memory in destructors.
+        poison_in_dtor: bool,
+        /// Sets whether to poison newly allocated memory.
+        poison_in_malloc: bool,
+        /// Sets whether to poison freed memory.
+        poison_in_free: bool,
+        /// Sets whether to report uses of uninitialized memory.
+        report_umrs: bool,
+    }
+}
+
+options! {
+    /// Runtime options for ThreadSanitizer.
+    ///
+    /// For more information about the options, see
+    /// <https://github.com/google/sanitizers/wiki/ThreadSanitizerFlags>.
+    TsanOptions, "TSAN_OPTIONS" {
+        /// Sets whether to exit after the first error is reported.
+        halt_on_error: bool,
+        /// Sets the path prefix of the files the reports are written to.
+        log_path: str,
+        /// Sets whether to symbolize the stack traces in the reports.
+        symbolize: bool,
+        /// Sets whether to call `abort` instead of `_exit` after reporting an
+        /// error.
+        abort_on_error: bool,
+        /// Sets the exit code used after reporting an error.
+        exitcode: i32,
+        /// Sets the verbosity level.
+        verbosity: i32,
+        /// Sets whether to report calls to functions that are not
+        /// async-signal-safe from signal handlers.
+        report_signal_unsafe: bool,
+        /// Sets the size of the memory access history per thread (from 0 for
+        /// 128K memory accesses up to 7 for 16M memory accesses).
+        history_size: u32,
+        /// Sets whether to report the second stack trace of lock-order
+        /// inversions.
+        second_deadlock_stack: bool,
+        /// Sets whether to detect deadlocks.
+        detect_deadlocks: bool,
+        /// Sets the path of the suppressions file.
+        suppressions: str,
+    }
+}
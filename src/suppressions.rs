@@ -0,0 +1,308 @@
+use crate::options::OptionsWriter;
+
+use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
+
+/// Defines the default suppressions for the sanitizers.
+///
+/// Emits the `__lsan_default_suppressions` and `__tsan_default_suppressions`
+/// hooks (for the `lsan` and `tsan` keys, respectively), which the sanitizer
+/// runtimes read in addition to the suppressions files set with the
+/// `suppressions` runtime option. The macro must be used at most once per
+/// sanitizer in the final binary.
+///
+/// The suppressions are given either as string literals (with one suppression
+/// per line) or as constant expressions of [`Suppressions`] (e.g., built with
+/// its builder methods), which are rendered at compile time. A set of
+/// [`Suppressions`] holds at most [`MAX_SUPPRESSIONS`] suppressions.
+///
+/// E.g.:
+///
+/// ```
+/// use sanitizers::suppressions::Suppressions;
+///
+/// sanitizers::default_suppressions!(
+///     lsan = "leak:libfontconfig\n",
+///     tsan = Suppressions::new()
+///         .race("third_party::cache::insert")
+///         .called_from_lib("libglib-2.0.so"),
+/// );
+/// ```
+#[macro_export]
+macro_rules! default_suppressions {
+    (@munch) => {};
+    (@munch $sanitizer:ident = $suppressions:literal $(, $($rest:tt)*)?) => {
+        $crate::default_suppressions!(@hook $sanitizer, literal $suppressions);
+        $crate::default_suppressions!(@munch $($($rest)*)?);
+    };
+    (@munch $sanitizer:ident = $suppressions:expr $(, $($rest:tt)*)?) => {
+        $crate::default_suppressions!(@hook $sanitizer, expr $suppressions);
+        $crate::default_suppressions!(@munch $($($rest)*)?);
+    };
+    (@hook lsan, $($suppressions:tt)+) => {
+        $crate::default_options!(@define __lsan_default_suppressions, $($suppressions)+);
+    };
+    (@hook tsan, $($suppressions:tt)+) => {
+        $crate::default_options!(@define __tsan_default_suppressions, $($suppressions)+);
+    };
+    (@hook $sanitizer:ident, $($suppressions:tt)+) => {
+        ::std::compile_error!(::std::concat!(
+            "unsupported sanitizer: `",
+            ::std::stringify!($sanitizer),
+            "` (expected `lsan` or `tsan`)"
+        ));
+    };
+    ($($suppressions:tt)+) => {
+        $crate::default_suppressions!(@munch $($suppressions)+);
+    };
+}
+
+/// A suppression, which matches reports by the given pattern (e.g., a function,
+/// source file, or module name, with `*` as a wildcard).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Suppression<'a> {
+    /// Suppresses data races (ThreadSanitizer) with any frame matching the
+    /// pattern.
+    Race(&'a str),
+    /// Suppresses data races (ThreadSanitizer) with the top frame matching the
+    /// pattern.
+    RaceTop(&'a str),
+    /// Suppresses thread leaks (ThreadSanitizer) of threads created in a frame
+    /// matching the pattern.
+    Thread(&'a str),
+    /// Suppresses mutex misuses (ThreadSanitizer) with any frame matching the
+    /// pattern.
+    Mutex(&'a str),
+    /// Suppresses signal-unsafe calls in signal handlers (ThreadSanitizer) with
+    /// any frame matching the pattern.
+    Signal(&'a str),
+    /// Suppresses lock-order inversions (ThreadSanitizer) with any frame matching
+    /// the pattern.
+    Deadlock(&'a str),
+    /// Ignores the interceptors (ThreadSanitizer) called from the library
+    /// matching the pattern.
+    CalledFromLib(&'a str),
+    /// Suppresses leaks (LeakSanitizer) with any frame of the allocation stack
+    /// trace matching the pattern.
+    Leak(&'a str),
+}
+
+impl<'a> Suppression<'a> {
+    /// Returns the suppression type as used in the suppressions files (e.g.,
+    /// `race`).
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Suppression::Race(_) => "race",
+            Suppression::RaceTop(_) => "race_top",
+            Suppression::Thread(_) => "thread",
+            Suppression::Mutex(_) => "mutex",
+            Suppression::Signal(_) => "signal",
+            Suppression::Deadlock(_) => "deadlock",
+            Suppression::CalledFromLib(_) => "called_from_lib",
+            Suppression::Leak(_) => "leak",
+        }
+    }
+
+    /// Returns the pattern the suppression matches reports by.
+    pub const fn pattern(&self) -> &'a str {
+        match *self {
+            Suppression::Race(pattern)
+            | Suppression::RaceTop(pattern)
+            | Suppression::Thread(pattern)
+            | Suppression::Mutex(pattern)
+            | Suppression::Signal(pattern)
+            | Suppression::Deadlock(pattern)
+            | Suppression::CalledFromLib(pattern)
+            | Suppression::Leak(pattern) => pattern,
+        }
+    }
+}
+
+impl Display for Suppression<'_> {
+    /// Renders the suppression in the form used by the suppressions files (e.g.,
+    /// `race:foo::bar`).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.kind(), self.pattern())
+    }
+}
+
+/// Maximum number of suppressions in a set of suppressions.
+pub const MAX_SUPPRESSIONS: usize = 64;
+
+/// A set of suppressions.
+///
+/// The suppressions are rendered in the form used by the suppressions files
+/// (i.e., one suppression per line) by the `Display` implementation, and can be
+/// embedded in the binary with the `default_suppressions!` macro. The set is
+/// stored inline (with room for up to [`MAX_SUPPRESSIONS`] suppressions), so it
+/// can be built in constant expressions.
+///
+/// Therefore, a set holds at most [`MAX_SUPPRESSIONS`] suppressions, and the
+/// builder methods panic when adding more (i.e., compilation fails when the set
+/// is built in a constant expression). Use a string literal (or a suppressions
+/// file) for longer lists. Also, every set has the size of a full set (about
+/// 1.5 KiB on 64-bit targets), so pass it by reference outside of constant
+/// expressions.
+#[derive(Clone, Copy)]
+pub struct Suppressions<'a> {
+    entries: [Suppression<'a>; MAX_SUPPRESSIONS],
+    len: usize,
+}
+
+impl<'a> Suppressions<'a> {
+    /// Creates a new empty set of suppressions.
+    pub const fn new() -> Self {
+        Suppressions {
+            entries: [Suppression::Race(""); MAX_SUPPRESSIONS],
+            len: 0,
+        }
+    }
+
+    /// Creates a new set of suppressions from the given suppressions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than [`MAX_SUPPRESSIONS`] suppressions.
+    pub const fn from_entries(entries: &[Suppression<'a>]) -> Self {
+        let mut suppressions = Self::new();
+        let mut i = 0;
+        while i < entries.len() {
+            suppressions = suppressions.entry(entries[i]);
+            i += 1;
+        }
+        suppressions
+    }
+
+    /// Adds the given suppression.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the set already has [`MAX_SUPPRESSIONS`] suppressions.
+    pub const fn entry(mut self, suppression: Suppression<'a>) -> Self {
+        assert!(self.len < MAX_SUPPRESSIONS, "too many suppressions");
+        self.entries[self.len] = suppression;
+        self.len += 1;
+        self
+    }
+
+    /// Adds a suppression for data races with any frame matching the pattern.
+    pub const fn race(self, pattern: &'a str) -> Self {
+        self.entry(Suppression::Race(pattern))
+    }
+
+    /// Adds a suppression for data races with the top frame matching the
+    /// pattern.
+    pub const fn race_top(self, pattern: &'a str) -> Self {
+        self.entry(Suppression::RaceTop(pattern))
+    }
+
+    /// Adds a suppression for thread leaks of threads created in a frame
+    /// matching the pattern.
+    pub const fn thread(self, pattern: &'a str) -> Self {
+        self.entry(Suppression::Thread(pattern))
+    }
+
+    /// Adds a suppression for mutex misuses with any frame matching the pattern.
+    pub const fn mutex(self, pattern: &'a str) -> Self {
+        self.entry(Suppression::Mutex(pattern))
+    }
+
+    /// Adds a suppression for signal-unsafe calls in signal handlers with any
+    /// frame matching the pattern.
+    pub const fn signal(self, pattern: &'a str) -> Self {
+        self.entry(Suppression::Signal(pattern))
+    }
+
+    /// Adds a suppression for lock-order inversions with any frame matching the
+    /// pattern.
+    pub const fn deadlock(self, pattern: &'a str) -> Self {
+        self.entry(Suppression::Deadlock(pattern))
+    }
+
+    /// Adds a suppression for the interceptors called from the library matching
+    /// the pattern.
+    pub const fn called_from_lib(self, pattern: &'a str) -> Self {
+        self.entry(Suppression::CalledFromLib(pattern))
+    }
+
+    /// Adds a suppression for leaks with any frame of the allocation stack trace
+    /// matching the pattern.
+    pub const fn leak(self, pattern: &'a str) -> Self {
+        self.entry(Suppression::Leak(pattern))
+    }
+
+    /// Returns the suppressions.
+    pub const fn entries(&self) -> &[Suppression<'a>] {
+        self.entries.split_at(self.len).0
+    }
+
+    /// Returns the length, in bytes, of the rendered suppressions.
+    pub const fn rendered_len(&self) -> usize {
+        let mut writer = OptionsWriter::new(&mut []);
+        self.write(&mut writer);
+        writer.len
+    }
+
+    /// Renders the suppressions into a null-terminated byte array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is not greater than the length of the rendered
+    /// suppressions.
+    pub const fn render<const N: usize>(&self) -> [u8; N] {
+        let mut buffer = [0; N];
+        let mut writer = OptionsWriter::new(&mut buffer);
+        self.write(&mut writer);
+        assert!(writer.len < N, "buffer too small for the suppressions");
+        buffer
+    }
+
+    const fn write(&self, writer: &mut OptionsWriter<'_>) {
+        let entries = self.entries();
+        let mut i = 0;
+        while i < entries.len() {
+            writer.write_str(entries[i].kind());
+            writer.write_byte(b':');
+            writer.write_str(entries[i].pattern());
+            writer.write_byte(b'\n');
+            i += 1;
+        }
+    }
+}
+
+impl Default for Suppressions<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Suppressions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Suppressions")
+            .field("entries", &self.entries())
+            .finish()
+    }
+}
+
+impl PartialEq for Suppressions<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries() == other.entries()
+    }
+}
+
+impl Eq for Suppressions<'_> {}
+
+impl Hash for Suppressions<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.entries().hash(state);
+    }
+}
+
+impl Display for Suppressions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for suppression in self.entries() {
+            writeln!(f, "{}", suppression)?;
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,4 @@
-/// ThreadSanitizer interface.
-///
-/// For more information about ThreadSanitizer, see
-/// https://clang.llvm.org/docs/ThreadSanitizer.html.
+use crate::common::StackTrace;
 use crate::ffi::tsan::*;
 
 use std::ffi::{CStr, CString};
@@ -17,7 +14,7 @@ pub struct TsanReportData {
     pub mutex_count: c_int,
     pub thread_count: c_int,
     pub unique_tid_count: c_int,
-    pub sleep_trace: Vec<*mut c_void>,
+    pub sleep_trace: StackTrace,
 }
 
 /// Struct to hold memory operation report data.
@@ -27,7 +24,7 @@ pub struct TsanReportMop {
     pub size: c_int,
     pub write: c_int,
     pub atomic: c_int,
-    pub trace: Vec<*mut c_void>,
+    pub trace: StackTrace,
 }
 
 /// Struct to hold location report data.
@@ -39,7 +36,7 @@ pub struct TsanReportLoc {
     pub tid: c_int,
     pub fd: c_int,
     pub suppressable: c_int,
-    pub trace: Vec<*mut c_void>,
+    pub trace: StackTrace,
 }
 
 /// Struct to hold mutex report data.
@@ -47,7 +44,7 @@ pub struct TsanReportMutex {
     pub mutex_id: u64,
     pub addr: *mut c_void,
     pub destroyed: c_int,
-    pub trace: Vec<*mut c_void>,
+    pub trace: StackTrace,
 }
 
 /// Struct to hold thread report data.
@@ -57,74 +54,130 @@ pub struct TsanReportThread {
     pub running: c_int,
     pub name: String,
     pub parent_tid: c_int,
-    pub trace: Vec<*mut c_void>,
+    pub trace: StackTrace,
 }
 
 /// Establishes a happens-before relation with a preceding acquire on the same
 /// address.
-pub fn acquire(addr: *mut c_void) {
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+pub unsafe fn acquire(addr: *mut c_void) {
     unsafe {
         __tsan_acquire(addr);
     }
 }
 
+/// Establishes a happens-before relation with a preceding acquire on the
+/// address of `value`.
+pub fn acquire_val<T: ?Sized>(value: &T) {
+    unsafe {
+        acquire(value as *const T as *mut c_void);
+    }
+}
+
 /// Establishes a happens-before relation with a subsequent release on the same
 /// address.
-pub fn release(addr: *mut c_void) {
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory.
+pub unsafe fn release(addr: *mut c_void) {
     unsafe {
         __tsan_release(addr);
     }
 }
 
+/// Establishes a happens-before relation with a subsequent release on the
+/// address of `value`.
+pub fn release_val<T: ?Sized>(value: &T) {
+    unsafe {
+        release(value as *const T as *mut c_void);
+    }
+}
+
 /// Annotate creation of a mutex.
-pub fn mutex_create(addr: *mut c_void, flags: c_uint) {
+///
+/// # Safety
+///
+/// `addr` must be the address of a mutex in the application memory.
+pub unsafe fn mutex_create(addr: *mut c_void, flags: c_uint) {
     unsafe {
         __tsan_mutex_create(addr, flags);
     }
 }
 
 /// Annotate destruction of a mutex.
-pub fn mutex_destroy(addr: *mut c_void, flags: c_uint) {
+///
+/// # Safety
+///
+/// `addr` must be the address of a mutex in the application memory.
+pub unsafe fn mutex_destroy(addr: *mut c_void, flags: c_uint) {
     unsafe {
         __tsan_mutex_destroy(addr, flags);
     }
 }
 
 /// Annotate start of lock operation.
-pub fn mutex_pre_lock(addr: *mut c_void, flags: c_uint) {
+///
+/// # Safety
+///
+/// `addr` must be the address of a mutex in the application memory.
+pub unsafe fn mutex_pre_lock(addr: *mut c_void, flags: c_uint) {
     unsafe {
         __tsan_mutex_pre_lock(addr, flags);
     }
 }
 
 /// Annotate end of lock operation.
-pub fn mutex_post_lock(addr: *mut c_void, flags: c_uint, recursion: c_int) {
+///
+/// # Safety
+///
+/// `addr` must be the address of a mutex in the application memory.
+pub unsafe fn mutex_post_lock(addr: *mut c_void, flags: c_uint, recursion: c_int) {
     unsafe {
         __tsan_mutex_post_lock(addr, flags, recursion);
     }
 }
 
 /// Annotate start of unlock operation.
-pub fn mutex_pre_unlock(addr: *mut c_void, flags: c_uint) -> c_int {
+///
+/// # Safety
+///
+/// `addr` must be the address of a mutex in the application memory.
+pub unsafe fn mutex_pre_unlock(addr: *mut c_void, flags: c_uint) -> c_int {
     unsafe { __tsan_mutex_pre_unlock(addr, flags) }
 }
 
 /// Annotate end of unlock operation.
-pub fn mutex_post_unlock(addr: *mut c_void, flags: c_uint) {
+///
+/// # Safety
+///
+/// `addr` must be the address of a mutex in the application memory.
+pub unsafe fn mutex_post_unlock(addr: *mut c_void, flags: c_uint) {
     unsafe {
         __tsan_mutex_post_unlock(addr, flags);
     }
 }
 
 /// Annotate start of notify operation.
-pub fn mutex_pre_signal(addr: *mut c_void, flags: c_uint) {
+///
+/// # Safety
+///
+/// `addr` must be the address of a mutex in the application memory.
+pub unsafe fn mutex_pre_signal(addr: *mut c_void, flags: c_uint) {
     unsafe {
         __tsan_mutex_pre_signal(addr, flags);
     }
 }
 
 /// Annotate end of notify operation.
-pub fn mutex_post_signal(addr: *mut c_void, flags: c_uint) {
+///
+/// # Safety
+///
+/// `addr` must be the address of a mutex in the application memory.
+pub unsafe fn mutex_post_signal(addr: *mut c_void, flags: c_uint) {
     unsafe {
         __tsan_mutex_post_signal(addr, flags);
     }
@@ -132,7 +185,11 @@ pub fn mutex_post_signal(addr: *mut c_void, flags: c_uint) {
 
 /// Annotate a region of code where lock/unlock/signal operation diverts to do
 /// something else unrelated to the mutex.
-pub fn mutex_pre_divert(addr: *mut c_void, flags: c_uint) {
+///
+/// # Safety
+///
+/// `addr` must be the address of a mutex in the application memory.
+pub unsafe fn mutex_pre_divert(addr: *mut c_void, flags: c_uint) {
     unsafe {
         __tsan_mutex_pre_divert(addr, flags);
     }
@@ -140,7 +197,11 @@ pub fn mutex_pre_divert(addr: *mut c_void, flags: c_uint) {
 
 /// Annotate end of a region of code where lock/unlock/signal operation diverts
 /// to do something else unrelated to the mutex.
-pub fn mutex_post_divert(addr: *mut c_void, flags: c_uint) {
+///
+/// # Safety
+///
+/// `addr` must be the address of a mutex in the application memory.
+pub unsafe fn mutex_post_divert(addr: *mut c_void, flags: c_uint) {
     unsafe {
         __tsan_mutex_post_divert(addr, flags);
     }
@@ -161,7 +222,11 @@ pub fn external_register_tag(object_type: &str) -> *mut c_void {
 }
 
 /// Registers a header for an external tag.
-pub fn external_register_header(tag: *mut c_void, header: &str) {
+///
+/// # Safety
+///
+/// `tag` must be a tag returned by `external_register_tag`.
+pub unsafe fn external_register_header(tag: *mut c_void, header: &str) {
     let header_cstr = CString::new(header).unwrap();
     unsafe {
         __tsan_external_register_header(tag, header_cstr.as_ptr());
@@ -169,21 +234,36 @@ pub fn external_register_header(tag: *mut c_void, header: &str) {
 }
 
 /// Assigns a tag to a heap object.
-pub fn external_assign_tag(addr: *mut c_void, tag: *mut c_void) {
+///
+/// # Safety
+///
+/// `addr` must point to a heap object and `tag` must be a tag returned by
+/// `external_register_tag`.
+pub unsafe fn external_assign_tag(addr: *mut c_void, tag: *mut c_void) {
     unsafe {
         __tsan_external_assign_tag(addr, tag);
     }
 }
 
 /// Annotates a logical read of the object at the specified address.
-pub fn external_read(addr: *mut c_void, caller_pc: *mut c_void, tag: *mut c_void) {
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory and `tag` must be a
+/// tag returned by `external_register_tag`.
+pub unsafe fn external_read(addr: *mut c_void, caller_pc: *mut c_void, tag: *mut c_void) {
     unsafe {
         __tsan_external_read(addr, caller_pc, tag);
     }
 }
 
 /// Annotates a logical write of the object at the specified address.
-pub fn external_write(addr: *mut c_void, caller_pc: *mut c_void, tag: *mut c_void) {
+///
+/// # Safety
+///
+/// `addr` must be an address in the application memory and `tag` must be a
+/// tag returned by `external_register_tag`.
+pub unsafe fn external_write(addr: *mut c_void, caller_pc: *mut c_void, tag: *mut c_void) {
     unsafe {
         __tsan_external_write(addr, caller_pc, tag);
     }
@@ -195,21 +275,36 @@ pub fn create_fiber(flags: c_uint) -> *mut c_void {
 }
 
 /// Destroys a fiber.
-pub fn destroy_fiber(fiber: *mut c_void) {
+///
+/// # Safety
+///
+/// `fiber` must be a fiber returned by `create_fiber` that has not been
+/// destroyed.
+pub unsafe fn destroy_fiber(fiber: *mut c_void) {
     unsafe {
         __tsan_destroy_fiber(fiber);
     }
 }
 
 /// Switches to a fiber.
-pub fn switch_to_fiber(fiber: *mut c_void, flags: c_uint) {
+///
+/// # Safety
+///
+/// `fiber` must be a fiber returned by `create_fiber` that has not been
+/// destroyed.
+pub unsafe fn switch_to_fiber(fiber: *mut c_void, flags: c_uint) {
     unsafe {
         __tsan_switch_to_fiber(fiber, flags);
     }
 }
 
 /// Sets a fiber name.
-pub fn set_fiber_name(fiber: *mut c_void, name: &str) {
+///
+/// # Safety
+///
+/// `fiber` must be a fiber returned by `create_fiber` that has not been
+/// destroyed.
+pub unsafe fn set_fiber_name(fiber: *mut c_void, name: &str) {
     let name_cstr = CString::new(name).unwrap();
     unsafe {
         __tsan_set_fiber_name(fiber, name_cstr.as_ptr());
@@ -235,8 +330,28 @@ pub fn flush_memory() {
     }
 }
 
+/// User-provided default suppressions (e.g., defined with
+/// `sanitizers::default_suppressions!`).
+pub fn default_suppressions() -> String {
+    unsafe {
+        let suppressions_ptr = __tsan_default_suppressions();
+        if suppressions_ptr.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(suppressions_ptr)
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+}
+
 /// Returns a report's description.
-pub fn get_report_data(report: *mut c_void) -> TsanReportData {
+///
+/// # Safety
+///
+/// `report` must be a report returned by `get_current_report` while it is
+/// being reported.
+pub unsafe fn get_report_data(report: *mut c_void) -> TsanReportData {
     unsafe {
         let mut description: *const c_char = std::ptr::null();
         let mut count: c_int = 0;
@@ -275,22 +390,32 @@ pub fn get_report_data(report: *mut c_void) -> TsanReportData {
             mutex_count,
             thread_count,
             unique_tid_count,
-            sleep_trace,
+            sleep_trace: StackTrace::new(sleep_trace),
         }
     }
 }
 
 /// Returns information about stack traces included in the report.
-pub fn get_report_stack(report: *mut c_void, idx: c_ulong) -> Vec<*mut c_void> {
+///
+/// # Safety
+///
+/// `report` must be a report returned by `get_current_report` while it is
+/// being reported.
+pub unsafe fn get_report_stack(report: *mut c_void, idx: c_ulong) -> StackTrace {
     unsafe {
         let mut trace: Vec<*mut c_void> = vec![std::ptr::null_mut(); 1024];
         __tsan_get_report_stack(report, idx, trace.as_mut_ptr(), 1024);
-        trace
+        StackTrace::new(trace)
     }
 }
 
 /// Returns information about memory operations included in the report.
-pub fn get_report_mop(report: *mut c_void, idx: c_ulong) -> TsanReportMop {
+///
+/// # Safety
+///
+/// `report` must be a report returned by `get_current_report` while it is
+/// being reported.
+pub unsafe fn get_report_mop(report: *mut c_void, idx: c_ulong) -> TsanReportMop {
     unsafe {
         let mut tid: c_int = 0;
         let mut addr: *mut c_void = std::ptr::null_mut();
@@ -317,13 +442,18 @@ pub fn get_report_mop(report: *mut c_void, idx: c_ulong) -> TsanReportMop {
             size,
             write,
             atomic,
-            trace,
+            trace: StackTrace::new(trace),
         }
     }
 }
 
 /// Returns information about locations included in the report.
-pub fn get_report_loc(report: *mut c_void, idx: c_ulong) -> TsanReportLoc {
+///
+/// # Safety
+///
+/// `report` must be a report returned by `get_current_report` while it is
+/// being reported.
+pub unsafe fn get_report_loc(report: *mut c_void, idx: c_ulong) -> TsanReportLoc {
     unsafe {
         let mut type_: *const c_char = std::ptr::null();
         let mut addr: *mut c_void = std::ptr::null_mut();
@@ -360,13 +490,18 @@ pub fn get_report_loc(report: *mut c_void, idx: c_ulong) -> TsanReportLoc {
             tid,
             fd,
             suppressable,
-            trace,
+            trace: StackTrace::new(trace),
         }
     }
 }
 
 /// Returns information about mutexes included in the report.
-pub fn get_report_mutex(report: *mut c_void, idx: c_ulong) -> TsanReportMutex {
+///
+/// # Safety
+///
+/// `report` must be a report returned by `get_current_report` while it is
+/// being reported.
+pub unsafe fn get_report_mutex(report: *mut c_void, idx: c_ulong) -> TsanReportMutex {
     unsafe {
         let mut mutex_id: u64 = 0;
         let mut addr: *mut c_void = std::ptr::null_mut();
@@ -387,13 +522,18 @@ pub fn get_report_mutex(report: *mut c_void, idx: c_ulong) -> TsanReportMutex {
             mutex_id,
             addr,
             destroyed,
-            trace,
+            trace: StackTrace::new(trace),
         }
     }
 }
 
 /// Returns information about threads included in the report.
-pub fn get_report_thread(report: *mut c_void, idx: c_ulong) -> TsanReportThread {
+///
+/// # Safety
+///
+/// `report` must be a report returned by `get_current_report` while it is
+/// being reported.
+pub unsafe fn get_report_thread(report: *mut c_void, idx: c_ulong) -> TsanReportThread {
     unsafe {
         let mut tid: c_int = 0;
         let mut os_id: u64 = 0;
@@ -424,13 +564,18 @@ pub fn get_report_thread(report: *mut c_void, idx: c_ulong) -> TsanReportThread
                 CStr::from_ptr(name).to_string_lossy().into_owned()
             },
             parent_tid,
-            trace,
+            trace: StackTrace::new(trace),
         }
     }
 }
 
 /// Returns information about unique thread IDs included in the report.
-pub fn get_report_unique_tid(report: *mut c_void, idx: c_ulong) -> c_int {
+///
+/// # Safety
+///
+/// `report` must be a report returned by `get_current_report` while it is
+/// being reported.
+pub unsafe fn get_report_unique_tid(report: *mut c_void, idx: c_ulong) -> c_int {
     unsafe {
         let mut tid: c_int = 0;
         __tsan_get_report_unique_tid(report, idx, &mut tid);
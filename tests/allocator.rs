@@ -0,0 +1,101 @@
+#![feature(cfg_sanitize)]
+
+use sanitizers::allocator;
+#[cfg(any(
+    sanitize = "address",
+    sanitize = "dataflow",
+    sanitize = "leak",
+    sanitize = "memory",
+    sanitize = "thread"
+))]
+use std::hint::black_box;
+use std::os::raw::c_void;
+
+/// Tests that the allocator counters account for allocations.
+#[cfg(any(
+    sanitize = "address",
+    sanitize = "dataflow",
+    sanitize = "leak",
+    sanitize = "memory",
+    sanitize = "thread"
+))]
+#[test]
+fn counters() {
+    const SIZE: usize = 1 << 20;
+
+    let before = allocator::current_allocated_bytes();
+    let data = black_box(vec![0u8; SIZE]);
+    let after = allocator::current_allocated_bytes();
+    // Other threads (e.g., of the test harness) may allocate and free
+    // concurrently.
+    assert!(after >= before + SIZE / 2);
+    assert!(allocator::heap_size() >= after);
+    assert!(allocator::estimated_allocated_size(SIZE) >= SIZE);
+    let _ = allocator::free_bytes();
+    let _ = allocator::unmapped_bytes();
+
+    drop(data);
+    assert!(allocator::current_allocated_bytes() <= after - SIZE / 2);
+    allocator::purge_allocator();
+}
+
+/// Tests that heap chunks are looked up.
+#[cfg(any(
+    sanitize = "address",
+    sanitize = "dataflow",
+    sanitize = "leak",
+    sanitize = "memory",
+    sanitize = "thread"
+))]
+#[test]
+fn ownership() {
+    let data = vec![0u8; 100];
+    let begin = data.as_ptr() as *const c_void;
+    let interior = begin.wrapping_add(50);
+    unsafe {
+        assert!(allocator::get_ownership(begin));
+        assert_eq!(allocator::get_allocated_size(begin), Some(100));
+        assert_eq!(allocator::get_allocated_begin(interior), Some(begin));
+    }
+    assert!(allocator::get_ownership_val(&data[..]));
+    assert_eq!(allocator::get_allocated_size_val(&data[..]), Some(100));
+    assert_eq!(allocator::get_allocated_begin_val(&data[50]), Some(begin));
+
+    let local = 0u64;
+    assert!(!allocator::get_ownership_val(&local));
+    assert_eq!(allocator::get_allocated_begin_val(&local), None);
+
+    drop(data);
+    unsafe {
+        assert!(!allocator::get_ownership(begin));
+        assert_eq!(allocator::get_allocated_size(begin), None);
+    }
+}
+
+/// Tests that the functions are callable without a sanitizer runtime.
+#[cfg(not(any(
+    sanitize = "address",
+    sanitize = "dataflow",
+    sanitize = "leak",
+    sanitize = "memory",
+    sanitize = "thread"
+)))]
+#[test]
+fn noop() {
+    let data = Box::new(0u64);
+    let begin = &*data as *const u64 as *const c_void;
+    assert_eq!(allocator::current_allocated_bytes(), 0);
+    assert_eq!(allocator::heap_size(), 0);
+    assert_eq!(allocator::free_bytes(), 0);
+    assert_eq!(allocator::unmapped_bytes(), 0);
+    assert_eq!(allocator::estimated_allocated_size(100), 100);
+    unsafe {
+        assert!(!allocator::get_ownership(begin));
+        assert_eq!(allocator::get_allocated_size(begin), None);
+        assert_eq!(allocator::get_allocated_begin(begin), None);
+    }
+    assert!(!allocator::get_ownership_val(&*data));
+    assert_eq!(allocator::get_allocated_size_val(&*data), None);
+    assert_eq!(allocator::get_allocated_begin_val(&*data), None);
+    allocator::purge_allocator();
+}
@@ -1,10 +1,16 @@
 #![feature(cfg_sanitize)]
+#![allow(clippy::bool_assert_comparison)]
 
-#[cfg(sanitize = "address")]
 use sanitizers::asan;
-#[cfg(sanitize = "address")]
+use sanitizers::options::AsanOptions;
 use std::os::raw::c_void;
 
+sanitizers::default_options!(
+    asan = AsanOptions::new()
+        .allow_user_poisoning(true)
+        .detect_stack_use_after_return(true)
+);
+
 /// Tests that memory regions can be poisoned and unpoisoned.
 #[cfg(sanitize = "address")]
 #[test]
@@ -13,16 +19,808 @@ fn basic() {
     let data_ptr = data.as_mut_ptr() as *const c_void;
 
     // Poison the memory region
-    asan::poison_memory_region(data_ptr, data.len());
+    unsafe { asan::poison_memory_region(data_ptr, data.len()) };
 
     // Check if the memory region is poisoned
-    let is_poisoned = asan::is_address_poisoned(data_ptr);
+    let is_poisoned = unsafe { asan::is_address_poisoned(data_ptr) };
     assert_eq!(is_poisoned, true);
 
     // Unpoison the memory region
-    asan::unpoison_memory_region(data_ptr, data.len());
+    unsafe { asan::unpoison_memory_region(data_ptr, data.len()) };
 
     // Check if the memory region is unpoisoned
-    let is_poisoned = asan::is_address_poisoned(data_ptr);
+    let is_poisoned = unsafe { asan::is_address_poisoned(data_ptr) };
+    assert_eq!(is_poisoned, false);
+}
+
+/// Tests that slices can be poisoned and unpoisoned.
+#[cfg(sanitize = "address")]
+#[test]
+fn slice() {
+    let data = [0u64; 8];
+
+    // Poison the second half of the slice
+    asan::poison_val(&data[4..]);
+
+    // Check that the first poisoned byte is at the start of the second half
+    let poisoned_offset = asan::region_is_poisoned_val(&data[..]);
+    assert_eq!(poisoned_offset, Some(32));
+    let poisoned_offset = asan::region_is_poisoned_val(&data[..4]);
+    assert_eq!(poisoned_offset, None);
+
+    // Unpoison the second half of the slice
+    asan::unpoison_val(&data[4..]);
+
+    // Check that the slice is unpoisoned
+    let poisoned_offset = asan::region_is_poisoned_val(&data[..]);
+    assert_eq!(poisoned_offset, None);
+}
+
+/// Tests that poison guards poison regions and restore them when dropped.
+#[cfg(sanitize = "address")]
+#[test]
+fn poison_guard() {
+    let data = Box::new([0u8; 64]);
+
+    // Poison a granule-aligned region
+    {
+        let guard = asan::PoisonGuard::new(&data[16..32]);
+        assert!(guard.is_fully_poisoned());
+        let poisoned_offset = asan::region_is_poisoned_val(&data[..]);
+        assert_eq!(poisoned_offset, Some(16));
+
+        // Nested guards do not unpoison the enclosing region when dropped
+        drop(asan::PoisonGuard::new(&data[20..24]));
+        let poisoned_offset = asan::region_is_poisoned_val(&data[16..32]);
+        assert_eq!(poisoned_offset, Some(0));
+    }
+    let poisoned_offset = asan::region_is_poisoned_val(&data[..]);
+    assert_eq!(poisoned_offset, None);
+
+    // Poison a region ending in a partially covered granule followed by
+    // addressable bytes
+    {
+        let guard = asan::PoisonGuard::new(&data[4..13]);
+        assert_eq!(guard.len(), 9);
+        assert_eq!(guard.poisoned_len(), 4);
+        assert!(!guard.is_fully_poisoned());
+    }
+    let poisoned_offset = asan::region_is_poisoned_val(&data[..]);
+    assert_eq!(poisoned_offset, None);
+
+    // Poison a region with a poisoned hole in the middle
+    asan::poison_val(&data[24..40]);
+    drop(asan::PoisonGuard::new(&data[..]));
+    let poisoned_offset = asan::region_is_poisoned_val(&data[..]);
+    assert_eq!(poisoned_offset, Some(24));
+    let poisoned_offset = asan::region_is_poisoned_val(&data[24..40]);
+    assert_eq!(poisoned_offset, Some(0));
+    let poisoned_offset = asan::region_is_poisoned_val(&data[40..]);
+    assert_eq!(poisoned_offset, None);
+    asan::unpoison_val(&data[24..40]);
+
+    // Poison a region with a hole ending in a partially addressable granule
+    asan::poison_val(&data[8..16]);
+    asan::unpoison_val(&data[8..11]);
+    drop(asan::PoisonGuard::new(&data[..]));
+    let poisoned_offset = asan::region_is_poisoned_val(&data[..]);
+    assert_eq!(poisoned_offset, Some(11));
+    let poisoned_offset = asan::region_is_poisoned_val(&data[16..]);
+    assert_eq!(poisoned_offset, None);
+    asan::unpoison_val(&data[8..16]);
+
+    // Poisoned regions are restored during unwinding
+    let result = std::panic::catch_unwind(|| {
+        let _guard = asan::PoisonGuard::new(&data[32..]);
+        panic!("unwinding");
+    });
+    assert!(result.is_err());
+    let poisoned_offset = asan::region_is_poisoned_val(&data[..]);
+    assert_eq!(poisoned_offset, None);
+}
+
+/// Tests that addresses can be located.
+#[cfg(sanitize = "address")]
+#[test]
+fn locate_address() {
+    static GLOBAL: [u64; 4] = [1, 2, 3, 4];
+
+    // Locate an address in a heap chunk
+    let data = Box::new([0u8; 100]);
+    let location = unsafe { asan::locate_address(data[10..].as_ptr() as *const c_void) };
+    assert_eq!(location.kind, asan::AsanLocationKind::Heap);
+    assert_eq!(location.region_address, data.as_ptr() as *mut c_void);
+    assert_eq!(location.region_size, data.len());
+
+    // Locate an address in a global variable (ASan may describe it using
+    // another global variable nearby)
+    let location = asan::locate_val(&GLOBAL[1]);
+    assert_eq!(location.kind, asan::AsanLocationKind::Global);
+    assert!(location.name.is_some());
+}
+
+/// Tests that allocation and deallocation stack traces can be retrieved.
+#[cfg(sanitize = "address")]
+#[test]
+fn heap_stacks() {
+    let data = Box::new([0u8; 100]);
+    let data_ptr = data.as_ptr() as *const c_void;
+
+    // Check that the allocation stack trace is available
+    let alloc_stack = asan::alloc_stack_val(&*data).unwrap();
+    assert!(!alloc_stack.trace.is_empty());
+    assert!(alloc_stack.trace.frames().iter().all(|pc| !pc.is_null()));
+
+    // Check that the deallocation stack trace is available after being freed
+    assert!(unsafe { asan::free_stack(data_ptr) }.is_none());
+    drop(data);
+    let free_stack = unsafe { asan::free_stack(data_ptr) }.unwrap();
+    assert!(!free_stack.trace.is_empty());
+    assert_eq!(free_stack.thread_id, alloc_stack.thread_id);
+}
+
+/// Tests that bug descriptions and access types are parsed.
+#[test]
+fn report() {
+    use asan::{AccessKind, BugType};
+
+    // Check that no error has been reported
+    assert!(asan::current_report().is_none());
+
+    // Parse bug descriptions
+    let bug_type = BugType::from_description("heap-use-after-free");
+    assert_eq!(bug_type, BugType::HeapUseAfterFree);
+    let bug_type = BugType::from_description("8-byte-read-heap-buffer-overflow");
+    assert_eq!(bug_type, BugType::HeapBufferOverflow);
+    let bug_type = BugType::from_description("dynamic-stack-buffer-overflow");
+    assert_eq!(bug_type, BugType::DynamicStackBufferOverflow);
+    let bug_type = BugType::from_description("wild-addr-write");
+    assert_eq!(bug_type, BugType::WildAddress);
+    let bug_type = BugType::from_description("odr-violation");
+    assert_eq!(bug_type, BugType::Unknown("odr-violation".to_owned()));
+
+    // Parse access types
+    assert_eq!(AccessKind::from_access_type(0), AccessKind::Read);
+    assert_eq!(AccessKind::from_access_type(1), AccessKind::Write);
+}
+
+/// Tests that error report callbacks can be registered and unregistered.
+#[test]
+fn error_report_callbacks() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            let calls = calls.clone();
+            asan::on_error_report(move |_| {
+                calls.fetch_add(1, Ordering::Relaxed);
+            })
+        })
+        .collect();
+
+    // Check that the callbacks are not called without an error being reported
+    for handle in handles {
+        handle.unregister();
+    }
+    assert_eq!(calls.load(Ordering::Relaxed), 0);
+}
+
+/// Tests that error report callbacks are called with the report when an error
+/// is reported.
+#[cfg(sanitize = "address")]
+#[test]
+fn error_report_dispatch() {
+    use std::process::Command;
+
+    // The report is fatal, so it is raised by this test in a child process.
+    if std::env::var_os("SANITIZERS_TEST_ERROR_REPORT_DISPATCH").is_some() {
+        let report_marker = |index: usize| {
+            move |report: &str| {
+                if report.contains("ERROR: AddressSanitizer: heap-buffer-overflow") {
+                    eprintln!("error report callback {} called with the report", index);
+                }
+            }
+        };
+        asan::on_error_report(report_marker(0));
+        asan::on_error_report(|_| panic!("error report callback 1 panicked"));
+        asan::on_error_report(report_marker(2)).unregister();
+        asan::on_error_report(report_marker(3));
+
+        let data = Box::new([0u8; 13]);
+        let ptr = data[8..].as_ptr() as *const c_void;
+        unsafe { asan::report_bad_access(ptr, 8, asan::AccessKind::Write) };
+        unreachable!();
+    }
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "error_report_dispatch", "--nocapture"])
+        .env("SANITIZERS_TEST_ERROR_REPORT_DISPATCH", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("error report callback 0 called with the report"));
+    assert!(stderr.contains("error report callback 1 panicked"));
+    assert!(!stderr.contains("error report callback 2 called"));
+    // The callbacks after a panicking callback are still called.
+    assert!(stderr.contains("error report callback 3 called with the report"));
+}
+
+/// Tests that death callbacks can be set and cleared.
+#[test]
+fn death_callback() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let called = Arc::new(AtomicBool::new(false));
+    let callback_called = called.clone();
+    asan::set_death_callback(move || callback_called.store(true, Ordering::Relaxed));
+
+    // Check that the callback is not called without an error being reported
+    asan::clear_death_callback();
+    assert!(!called.load(Ordering::Relaxed));
+}
+
+/// Tests that death callbacks are called before the process is terminated
+/// after an error is reported, and that panics in them are contained.
+#[cfg(sanitize = "address")]
+#[test]
+fn death_callback_on_error() {
+    use std::process::Command;
+
+    // The report is fatal, so it is raised by this test in a child process.
+    if let Some(mode) = std::env::var_os("SANITIZERS_TEST_DEATH_CALLBACK_ON_ERROR") {
+        asan::set_death_callback(|| eprintln!("replaced death callback called"));
+        if mode == "panic" {
+            asan::set_death_callback(|| panic!("death callback panicked"));
+        } else {
+            asan::set_death_callback(|| eprintln!("death callback called"));
+        }
+
+        let data = Box::new([0u8; 13]);
+        let ptr = data[8..].as_ptr() as *const c_void;
+        unsafe { asan::report_bad_access(ptr, 8, asan::AccessKind::Write) };
+        unreachable!();
+    }
+    let run = |mode: &str| {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "death_callback_on_error", "--nocapture"])
+            .env("SANITIZERS_TEST_DEATH_CALLBACK_ON_ERROR", mode)
+            .output()
+            .unwrap();
+        // The runtime terminates the process with its exit code.
+        assert_eq!(output.status.code(), Some(1));
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    let stderr = run("print");
+    assert!(stderr.contains("ERROR: AddressSanitizer: heap-buffer-overflow"));
+    assert!(stderr.contains("death callback called"));
+    assert!(!stderr.contains("replaced death callback called"));
+
+    // A panic in the callback does not unwind into the runtime.
+    let stderr = run("panic");
+    assert!(stderr.contains("death callback panicked"));
+    assert!(!stderr.contains("panic in a function that cannot unwind"));
+}
+
+/// Tests that bad accesses are reported.
+#[cfg(sanitize = "address")]
+#[test]
+fn bad_access_report() {
+    use std::process::Command;
+
+    // The report is fatal, so it is raised by this test in a child process.
+    if std::env::var_os("SANITIZERS_TEST_BAD_ACCESS_REPORT").is_some() {
+        let data = Box::new([0u8; 13]);
+        let ptr = data[8..].as_ptr() as *const c_void;
+        unsafe { asan::report_bad_access(ptr, 8, asan::AccessKind::Write) };
+        unreachable!();
+    }
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "bad_access_report", "--nocapture"])
+        .env("SANITIZERS_TEST_BAD_ACCESS_REPORT", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("ERROR: AddressSanitizer: heap-buffer-overflow"));
+    assert!(stderr.contains("WRITE of size 8"));
+    assert!(stderr.contains("is located 0 bytes after 13-byte region"));
+}
+
+/// Tests that the default options are defined.
+#[cfg(sanitize = "address")]
+#[test]
+fn default_options() {
+    assert_eq!(
+        asan::default_options(),
+        "detect_stack_use_after_return=1:allow_user_poisoning=1"
+    );
+}
+
+/// Tests that addresses can be checked against the fake stack frames.
+#[cfg(sanitize = "address")]
+#[test]
+fn fake_stack() {
+    use std::sync::{mpsc, Barrier};
+
+    #[inline(never)]
+    fn with_local(f: &dyn Fn(*const c_void)) {
+        // The frames of functions with inline assembly (e.g., `black_box`) are
+        // not allocated in the fake stack.
+        let local = [0u8; 64];
+        f(local.as_ptr() as *const c_void);
+    }
+
+    let fake_stack = asan::current_fake_stack().expect("no fake stack");
+    with_local(&|addr| {
+        let frame = unsafe { fake_stack.contains(addr) }.expect("not in a fake frame");
+        assert!(frame.begin as usize <= addr as usize);
+        assert!((addr as usize) < frame.end as usize);
+        assert!(!frame.real_stack.is_null());
+    });
+    let data = Box::new([0u8; 64]);
+    assert!(fake_stack.contains_val(&*data).is_none());
+
+    // The fake stack of another thread can be inspected while it is alive
+    let barrier = Barrier::new(2);
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            with_local(&|addr| {
+                let fake_stack = asan::current_fake_stack().unwrap();
+                sender
+                    .send((fake_stack.as_ptr() as usize, addr as usize))
+                    .unwrap();
+                barrier.wait();
+            })
+        });
+        let (ptr, addr) = receiver.recv().unwrap();
+        let fake_stack = unsafe { asan::FakeStack::from_ptr(ptr as *mut c_void) }.unwrap();
+        assert_ne!(fake_stack, asan::current_fake_stack().unwrap());
+        assert!(unsafe { fake_stack.contains(addr as *const c_void) }.is_some());
+        barrier.wait();
+    });
+}
+
+/// Tests that the poisoning of the stack frames that are left without returning
+/// is cleaned up.
+#[cfg(sanitize = "address")]
+#[test]
+fn handle_no_return() {
+    use std::hint::black_box;
+
+    #[inline(never)]
+    fn jump(guard: Option<asan::NoReturnGuard>) {
+        match guard {
+            Some(guard) => drop(black_box(guard)),
+            None => asan::handle_no_return(),
+        }
+    }
+
+    // The frames of functions with inline assembly are not allocated in the
+    // fake stack, which is not cleaned up.
+    unsafe { std::arch::asm!("") };
+    let local = [0u8; 64];
+    for guard in [None, Some(asan::NoReturnGuard::new())] {
+        asan::poison_val(&local);
+        assert_eq!(asan::region_is_poisoned_val(&local), Some(0));
+        jump(guard);
+        assert_eq!(asan::region_is_poisoned_val(&local), None);
+    }
+}
+
+/// Tests that the shadow bytes of memory regions can be inspected.
+#[cfg(sanitize = "address")]
+#[test]
+fn shadow_bytes() {
+    use asan::ShadowByte;
+
+    let mapping = asan::shadow_mapping().unwrap();
+    assert_eq!(mapping.granularity(), asan::SHADOW_GRANULARITY);
+
+    // Heap chunks are granule-aligned and followed by a redzone
+    let data = Box::new([0u8; 13]);
+    let shadow = unsafe { asan::shadow_bytes(data.as_ptr() as *const c_void, 24) };
+    assert_eq!(shadow.addr(), data.as_ptr() as *const c_void);
+    assert_eq!(shadow.shadow_addr(), mapping.shadow_address(shadow.addr()));
+    assert_eq!(
+        shadow.as_slice(),
+        [
+            ShadowByte::Addressable,
+            ShadowByte::PartiallyAddressable(5),
+            ShadowByte::HeapLeftRedzone,
+        ]
+    );
+    assert_eq!(
+        shadow.to_string(),
+        format!("{:#014x}: 00 05 fa\n", shadow.shadow_addr() as usize)
+    );
+
+    // Poisoned granules are reported as poisoned by user
+    let data = Box::new([0u8; 32]);
+    {
+        let _guard = asan::PoisonGuard::new(&data[8..24]);
+        let shadow = asan::shadow_bytes_val(&data[..]).into_vec();
+        assert_eq!(
+            shadow,
+            [
+                ShadowByte::Addressable,
+                ShadowByte::UserPoisoned,
+                ShadowByte::UserPoisoned,
+                ShadowByte::Addressable,
+            ]
+        );
+    }
+    let shadow = asan::shadow_bytes_val(&data[..]);
+    assert!(shadow
+        .as_slice()
+        .iter()
+        .all(|byte| *byte == ShadowByte::Addressable));
+
+    // Raw shadow bytes round-trip
+    for byte in 0..=u8::MAX {
+        assert_eq!(ShadowByte::from_byte(byte).to_byte(), byte);
+    }
+}
+
+/// Tests that the inline poison checks agree with the runtime.
+#[cfg(sanitize = "address")]
+#[test]
+fn fast() {
+    let data = Box::new([0u8; 613]);
+    let check = |data: &[u8]| {
+        for (i, byte) in data.iter().enumerate() {
+            let addr = byte as *const u8 as *const c_void;
+            assert_eq!(
+                unsafe { asan::fast::is_poisoned(addr) },
+                unsafe { asan::is_address_poisoned(addr) },
+                "offset {}",
+                i
+            );
+            for j in i..=data.len() {
+                assert_eq!(
+                    asan::fast::check_range(&data[i..j]),
+                    asan::region_is_poisoned_val(&data[i..j]),
+                    "range {}..{}",
+                    i,
+                    j
+                );
+            }
+        }
+    };
+
+    // Unpoisoned memory
+    check(&data[..]);
+
+    // Poisoned memory, including partially covered granules
+    {
+        let _guard = asan::PoisonGuard::new(&data[5..27]);
+        check(&data[..]);
+    }
+    {
+        let _guard = asan::PoisonGuard::new(&data[300..]);
+        check(&data[..]);
+    }
+
+    // Heap redzone after the end of the chunk
+    let addr = unsafe { data.as_ptr().add(data.len()) } as *const c_void;
+    assert!(unsafe { asan::fast::is_poisoned(addr) });
+}
+
+/// Tests that the spare capacity of vectors is annotated as a container.
+#[cfg(sanitize = "address")]
+#[test]
+fn asan_vec() {
+    let is_poisoned = |vec: &asan::AsanVec<u32>, index: usize| unsafe {
+        asan::is_address_poisoned(vec.as_ptr().add(index) as *const c_void)
+    };
+
+    let mut vec = asan::AsanVec::with_capacity(4);
+    assert!(vec.verify());
+    assert!(is_poisoned(&vec, 0));
+    vec.push(1);
+    vec.push(2);
+    assert_eq!(vec.as_slice(), [1, 2]);
+    assert!(vec.verify());
+    assert!(!is_poisoned(&vec, 1));
+    assert!(is_poisoned(&vec, 2));
+
+    // Spare capacity is reported as a container overflow
+    let shadow = unsafe { asan::shadow_bytes(vec.as_ptr().add(2) as *const c_void, 8) };
+    assert_eq!(shadow.as_slice(), [asan::ShadowByte::ContainerOverflow]);
+
+    // Growing the vector annotates the new buffer
+    vec.extend([3, 4, 5]);
+    assert!(vec.capacity() > 5);
+    assert!(vec.verify());
+    assert!(!is_poisoned(&vec, 4));
+    assert!(is_poisoned(&vec, 5));
+
+    // Shrinking the vector poisons the removed elements
+    assert_eq!(vec.pop(), Some(5));
+    assert_eq!(vec.remove(0), 1);
+    vec.insert(1, 6);
+    vec.truncate(3);
+    assert_eq!(vec.as_slice(), [2, 6, 3]);
+    assert!(vec.verify());
+    assert!(is_poisoned(&vec, 3));
+    vec.shrink_to_fit();
+    vec.reserve(10);
+    assert!(vec.verify());
+    vec.clear();
+    assert!(vec.verify());
+    assert!(is_poisoned(&vec, 0));
+
+    // Converting the vector removes the annotations
+    vec.push(7);
+    let mut vec = vec.into_vec();
+    let poisoned_offset = asan::region_is_poisoned_val(vec.spare_capacity_mut());
+    assert_eq!(poisoned_offset, None);
+}
+
+/// Tests that only the live window of ring buffers is addressable, including
+/// when the window wraps around.
+#[cfg(sanitize = "address")]
+#[test]
+fn ring_annotations() {
+    let storage = vec![0u64; 8].into_boxed_slice();
+    let is_poisoned = |index: usize| unsafe {
+        asan::is_address_poisoned(storage.as_ptr().add(index) as *const c_void)
+    };
+    let poisoned = || (0..8).filter(|&i| is_poisoned(i)).collect::<Vec<_>>();
+
+    // The storage of an empty ring is unaddressable
+    let mut ring = unsafe { asan::RingAnnotations::new(storage.as_ptr(), storage.len()) };
+    assert!(ring.verify());
+    assert_eq!(poisoned(), [0, 1, 2, 3, 4, 5, 6, 7]);
+
+    // Both the head and tail spare regions are unaddressable
+    ring.set_window(2, 3);
+    assert!(ring.verify());
+    assert_eq!(poisoned(), [0, 1, 5, 6, 7]);
+    let shadow = unsafe { asan::shadow_bytes(storage.as_ptr().add(1) as *const c_void, 8) };
+    assert_eq!(shadow.as_slice(), [asan::ShadowByte::ContainerOverflow]);
+    ring.set_window(5, 3);
+    assert!(ring.verify());
+    assert_eq!(poisoned(), [0, 1, 2, 3, 4]);
+
+    // The spare region between the tail and the head of a wrapped ring is
+    // unaddressable
+    ring.set_window(6, 4);
+    assert!(ring.verify());
+    assert_eq!(poisoned(), [2, 3, 4, 5]);
+    ring.set_window(7, 8);
+    assert!(ring.verify());
+    assert_eq!(poisoned(), []);
+    ring.set_window(3, 0);
+    assert!(ring.verify());
+    assert_eq!(poisoned(), [0, 1, 2, 3, 4, 5, 6, 7]);
+
+    // Dropping the annotations makes the storage addressable
+    ring.set_window(4, 6);
+    drop(ring);
+    assert_eq!(asan::region_is_poisoned_val(&storage[..]), None);
+}
+
+/// Tests that the allocation context of heap chunks can be updated.
+#[cfg(sanitize = "address")]
+#[test]
+fn update_allocation_context() {
+    let data = Box::new([0u8; 64]);
+    let ptr = data.as_ptr() as *const c_void;
+    let alloc_stack = asan::alloc_stack_val(&*data).unwrap();
+    assert!(unsafe { asan::update_allocation_context(ptr) });
+    let context = asan::alloc_stack_val(&*data).unwrap();
+    assert_ne!(context.trace.frames()[0], alloc_stack.trace.frames()[0]);
+    let local = 0u64;
+    assert!(!asan::update_allocation_context_val(&local));
+}
+
+/// Tests that the redzones and the quarantine of the redzone allocator are
+/// poisoned.
+#[cfg(sanitize = "address")]
+#[test]
+fn redzone_allocator() {
+    use std::alloc::{GlobalAlloc, Layout, System};
+
+    let allocator = asan::RedzoneAllocator::new(System)
+        .left_redzone(32)
+        .right_redzone(32)
+        .quarantine_size(1024);
+    let is_poisoned = |ptr: *mut u8, offset: isize| unsafe {
+        asan::is_address_poisoned(ptr.offset(offset) as *const c_void)
+    };
+
+    // Only the allocation is addressable
+    let layout = Layout::from_size_align(13, 8).unwrap();
+    let ptr = unsafe { allocator.alloc(layout) };
+    assert!(!ptr.is_null());
+    assert!(is_poisoned(ptr, -32));
+    assert!(is_poisoned(ptr, -1));
+    let region = unsafe { std::slice::from_raw_parts(ptr, 13) };
+    assert_eq!(asan::region_is_poisoned_val(region), None);
+    assert!(is_poisoned(ptr, 13));
+    assert!(is_poisoned(ptr, 13 + 31));
+    let shadow = unsafe { asan::shadow_bytes(ptr.offset(-8) as *const c_void, 8) };
+    assert_eq!(shadow.as_slice(), [asan::ShadowByte::UserPoisoned]);
+
+    // Freed blocks are poisoned until they are evicted from the quarantine
+    unsafe { allocator.dealloc(ptr, layout) };
+    assert!(is_poisoned(ptr, 0));
+    assert_eq!(allocator.quarantined_size(), 80);
+    assert!(unsafe { asan::free_stack(ptr as *const c_void) }.is_none());
+    let ptrs = (0..12)
+        .map(|_| unsafe { allocator.alloc(layout) })
+        .collect::<Vec<_>>();
+    for &ptr in &ptrs {
+        unsafe { allocator.dealloc(ptr, layout) };
+    }
+    assert_eq!(allocator.quarantined_size(), 960);
+    assert!(unsafe { asan::free_stack(ptr as *const c_void) }.is_some());
+    assert!(is_poisoned(ptrs[0], 0));
+    allocator.flush_quarantine();
+    assert_eq!(allocator.quarantined_size(), 0);
+    assert!(unsafe { asan::free_stack(ptrs[11] as *const c_void) }.is_some());
+
+    // Allocations are aligned
+    let layout = Layout::from_size_align(100, 64).unwrap();
+    let ptr = unsafe { allocator.alloc(layout) };
+    assert_eq!(ptr as usize % 64, 0);
+    assert!(is_poisoned(ptr, -1));
+    assert!(is_poisoned(ptr, 100));
+    unsafe { allocator.dealloc(ptr, layout) };
+}
+
+/// Tests that only the allocations of the arena are addressable.
+#[cfg(sanitize = "address")]
+#[test]
+fn asan_arena() {
+    let is_poisoned = |ptr: *const u8, offset: usize| unsafe {
+        asan::is_address_poisoned(ptr.add(offset) as *const c_void)
+    };
+
+    let mut arena = asan::AsanArena::new(256);
+    let chunk = arena.as_ptr() as *mut c_void;
+    assert_eq!(unsafe { asan::region_is_poisoned(chunk, 256) }, Some(chunk));
+
+    // Padding and redzones between allocations are poisoned
+    let a = arena.alloc_slice_copy(&[1u8; 13]).unwrap().as_ptr();
+    let b = arena.alloc(7u64).unwrap() as *const u64 as *const u8;
+    let c = arena.alloc_slice_copy(&[2u32; 5]).unwrap().as_ptr() as *const u8;
+    assert_eq!(
+        asan::region_is_poisoned_val(unsafe { std::slice::from_raw_parts(a, 13) }),
+        None
+    );
+    assert!(is_poisoned(a, 13));
+    assert!(b as usize >= a as usize + 13 + 16);
+    assert!(is_poisoned(b, 8));
+    assert!(is_poisoned(b, 8 + 15));
+    assert!(!is_poisoned(c, 19));
+    assert!(is_poisoned(c, 20));
+    assert_eq!(b as usize % 8, 0);
+    let aligned = arena.alloc_layout(std::alloc::Layout::from_size_align(4, 64).unwrap());
+    assert_eq!(aligned.unwrap().as_ptr() as usize % 64, 0);
+
+    // Exhausting the chunk does not unpoison anything
+    assert!(arena.alloc([0u8; 256]).is_err());
+    assert!(arena.allocated_bytes() <= arena.capacity());
+
+    // Resetting the arena poisons the entire chunk
+    arena.reset();
+    assert_eq!(arena.allocated_bytes(), 0);
+    assert!(is_poisoned(a, 0));
+    assert!(is_poisoned(b, 0));
+    let d = arena.alloc(1u8).unwrap() as *const u8;
+    assert_eq!(d, a);
+    assert!(is_poisoned(d, 1));
+    assert!(is_poisoned(c, 0));
+}
+
+/// Tests that the objects of the pool are poisoned while they are in the pool.
+#[cfg(sanitize = "address")]
+#[test]
+fn poisoned_pool() {
+    // The innermost frame of the allocation context set by
+    // `update_allocation_context`.
+    let update_context_frame = || {
+        let data = Box::new(0u64);
+        asan::update_allocation_context_val(&*data);
+        asan::alloc_stack_val(&*data).unwrap().trace.frames()[0]
+    };
+    let is_poisoned = |ptr: *const Vec<u8>| unsafe {
+        asan::region_is_poisoned(ptr as *mut c_void, size_of::<Vec<u8>>()).is_some()
+    };
+
+    let pool = asan::PoisonedPool::new();
+    pool.put(vec![1u8; 32]);
+    assert_eq!(pool.len(), 1);
+    let item = pool.get().unwrap();
+    let ptr = &*item as *const Vec<u8>;
+    drop(item);
+    assert!(is_poisoned(ptr));
+
+    // Checking out an object unpoisons it and updates its allocation context
+    let mut item = pool.get().unwrap();
+    assert!(pool.is_empty());
+    assert_eq!(&*item as *const Vec<u8>, ptr);
+    assert!(!is_poisoned(ptr));
+    let context = asan::alloc_stack_val(&*item).unwrap().trace;
+    assert_eq!(context.frames()[0], update_context_frame());
+    item[0] = 2;
+    drop(item);
+    assert!(is_poisoned(ptr));
+    let shadow = unsafe { asan::shadow_bytes(ptr as *const c_void, 8) };
+    assert_eq!(shadow.as_slice(), [asan::ShadowByte::UserPoisoned]);
+
+    // New objects are created when the pool is empty
+    let a = pool.get_or_else(|| vec![3u8; 32]);
+    let b = pool.get_or_else(|| vec![3u8; 32]);
+    assert_eq!((a[0], b[0]), (2, 3));
+    drop((a, b));
+    assert_eq!(pool.len(), 2);
+    let detached = pool.get().unwrap().detach();
+    assert_eq!(detached.len(), 32);
+    assert_eq!(pool.len(), 1);
+}
+
+/// Tests that the interface is inert when built without AddressSanitizer.
+#[cfg(not(sanitize = "address"))]
+#[test]
+fn noop() {
+    let mut data = vec![0u8; 100];
+    let data_ptr = data.as_mut_ptr() as *const c_void;
+
+    // Poisoning the memory region has no effect
+    unsafe { asan::poison_memory_region(data_ptr, data.len()) };
+    let is_poisoned = unsafe { asan::is_address_poisoned(data_ptr) };
     assert_eq!(is_poisoned, false);
+    let poisoned_addr = unsafe { asan::region_is_poisoned(data_ptr as *mut c_void, data.len()) };
+    assert_eq!(poisoned_addr, None);
+    asan::poison_val(&data[..]);
+    let poisoned_offset = asan::region_is_poisoned_val(&data[..]);
+    assert_eq!(poisoned_offset, None);
+    let guard = asan::PoisonGuard::new(&data[..]);
+    assert_eq!(guard.poisoned_len(), 0);
+    drop(guard);
+    assert_eq!(
+        asan::locate_val(&data[..]).kind,
+        asan::AsanLocationKind::Unknown(String::new())
+    );
+    assert!(asan::alloc_stack_val(&data[..]).is_none());
+    assert!(asan::current_fake_stack().is_none());
+    asan::handle_no_return();
+    drop(asan::NoReturnGuard::new());
+    unsafe { asan::report_bad_access(data_ptr, data.len(), asan::AccessKind::Read) };
+    assert!(!asan::update_allocation_context_val(&data[..]));
+    let arena = asan::AsanArena::new(64);
+    assert_eq!(arena.alloc(1u8), Ok(&mut 1));
+    assert_eq!(arena.alloc([0u8; 64]), Err([0u8; 64]));
+    let pool = asan::PoisonedPool::new();
+    pool.put(1u8);
+    assert_eq!(*pool.get().unwrap(), 1);
+    let allocator = asan::RedzoneAllocator::new(std::alloc::System);
+    let layout = std::alloc::Layout::new::<u64>();
+    unsafe {
+        let ptr = std::alloc::GlobalAlloc::alloc(&allocator, layout);
+        ptr.write(1);
+        std::alloc::GlobalAlloc::dealloc(&allocator, ptr, layout);
+    }
+    assert!(asan::shadow_mapping().is_none());
+    assert!(asan::shadow_bytes_val(&data[..]).is_empty());
+    assert!(!unsafe { asan::fast::is_poisoned(data_ptr) });
+    assert_eq!(asan::fast::check_range(&data[..]), None);
+    let mut vec = asan::AsanVec::with_capacity(4);
+    vec.push(1);
+    assert!(vec.verify());
+    assert_eq!(vec.into_vec(), [1]);
+    let mut ring = unsafe { asan::RingAnnotations::new(data.as_ptr(), data.len()) };
+    ring.set_window(90, 20);
+    assert!(ring.verify());
+
+    // No error has been reported
+    assert_eq!(asan::report_present(), false);
+    assert_eq!(asan::get_report_description(), "");
 }
@@ -0,0 +1,188 @@
+#![feature(cfg_sanitize)]
+
+use sanitizers::common;
+use std::collections::HashSet;
+use std::os::raw::c_void;
+
+/// Tests that the report path is set and retrieved.
+#[cfg(any(
+    sanitize = "address",
+    sanitize = "dataflow",
+    sanitize = "leak",
+    sanitize = "memory",
+    sanitize = "thread"
+))]
+#[test]
+fn report_path() {
+    let path_prefix = std::env::temp_dir().join("sanitizers-common-report");
+    let path_prefix = path_prefix.to_str().unwrap();
+    common::set_report_path(path_prefix);
+    let report_path = common::get_report_path().unwrap();
+    common::set_report_path("stderr");
+    let _ = std::fs::remove_file(&report_path);
+    assert_eq!(
+        report_path,
+        format!("{}.{}", path_prefix, std::process::id())
+    );
+}
+
+/// Tests that the crash state is acquired only once.
+#[test]
+fn acquire_crash_state() {
+    use std::process::Command;
+
+    // The crash state is process-wide and cannot be released, so it is
+    // acquired by this test in a child process.
+    if std::env::var_os("SANITIZERS_TEST_ACQUIRE_CRASH_STATE").is_some() {
+        assert!(common::acquire_crash_state());
+        assert!(!common::acquire_crash_state());
+        return;
+    }
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "acquire_crash_state", "--nocapture"])
+        .env("SANITIZERS_TEST_ACQUIRE_CRASH_STATE", "1")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 passed"));
+}
+
+/// Tests that the module and offset of a PC are retrieved.
+#[cfg(any(
+    sanitize = "address",
+    sanitize = "dataflow",
+    sanitize = "leak",
+    sanitize = "memory",
+    sanitize = "thread"
+))]
+#[test]
+fn get_module_and_offset_for_pc() {
+    let pc = get_module_and_offset_for_pc as fn() as *const c_void;
+    let (module_path, offset) = common::get_module_and_offset_for_pc(pc).unwrap();
+    let current_exe = std::env::current_exe().unwrap();
+    assert_eq!(
+        std::fs::canonicalize(module_path).unwrap(),
+        std::fs::canonicalize(current_exe).unwrap()
+    );
+    assert!(offset > 0);
+    assert!(common::get_module_and_offset_for_pc(std::ptr::null()).is_none());
+}
+
+/// Global variable to be symbolized.
+#[cfg(any(
+    sanitize = "address",
+    sanitize = "dataflow",
+    sanitize = "leak",
+    sanitize = "memory",
+    sanitize = "thread"
+))]
+static SYMBOLIZED_GLOBAL: [u64; 4] = [1, 2, 3, 4];
+
+/// Tests that PCs (program counters) are symbolized.
+#[cfg(any(
+    sanitize = "address",
+    sanitize = "dataflow",
+    sanitize = "leak",
+    sanitize = "memory",
+    sanitize = "thread"
+))]
+#[test]
+fn symbolize() {
+    // The instruction before the PC is symbolized, so the PC right after the
+    // entry of the function is its first instruction.
+    let addr = symbolize as fn() as *const c_void;
+    let pc = addr.wrapping_add(1);
+    let frames = common::symbolize(pc);
+    let frame = frames.last().unwrap();
+    assert!(frame.function.as_deref().unwrap().ends_with("symbolize"));
+    assert!(!frame.inlined);
+    assert_eq!(
+        (frame.module.clone().unwrap(), frame.offset.unwrap()),
+        common::get_module_and_offset_for_pc(addr).unwrap()
+    );
+    assert!(frame
+        .to_string()
+        .starts_with(frame.function.as_deref().unwrap()));
+    assert!(common::symbolize(std::ptr::null()).is_empty());
+
+    let global = common::symbolize_global(
+        std::hint::black_box(&SYMBOLIZED_GLOBAL[2]) as *const u64 as *const c_void,
+    )
+    .unwrap();
+    assert!(global.name.ends_with("SYMBOLIZED_GLOBAL"));
+    let heap = Box::new(0u64);
+    assert!(common::symbolize_global(&*heap as *const u64 as *const c_void).is_none());
+}
+
+/// Tests that stack traces store only the real frames, and are iterated,
+/// compared and formatted.
+#[test]
+fn stack_trace() {
+    let pc = (stack_trace as fn() as *mut c_void).wrapping_add(1);
+    let mut frames = vec![std::ptr::null_mut(); 1024];
+    frames[0] = pc;
+    frames[1] = pc;
+    let trace = common::StackTrace::new(frames);
+    assert_eq!(trace.len(), 2);
+    assert_eq!(trace.iter().collect::<Vec<_>>(), vec![pc, pc]);
+    assert_eq!(trace.clone().into_iter().count(), 2);
+
+    let traces: HashSet<common::StackTrace> =
+        [trace.clone(), common::StackTrace::new(vec![pc, pc])]
+            .into_iter()
+            .collect();
+    assert_eq!(traces.len(), 1);
+
+    let display = trace.to_string();
+    let lines: Vec<&str> = display.lines().collect();
+    assert!(lines[0].starts_with("    #0 0x"));
+    assert!(lines
+        .last()
+        .unwrap()
+        .starts_with(&format!("    #{} 0x", lines.len() - 1)));
+    #[cfg(any(
+        sanitize = "address",
+        sanitize = "dataflow",
+        sanitize = "leak",
+        sanitize = "memory",
+        sanitize = "thread"
+    ))]
+    {
+        assert_eq!(trace.symbolize().len(), lines.len());
+        assert!(lines[0].contains("stack_trace"));
+    }
+    #[cfg(not(any(
+        sanitize = "address",
+        sanitize = "dataflow",
+        sanitize = "leak",
+        sanitize = "memory",
+        sanitize = "thread"
+    )))]
+    assert_eq!(display, format!("    #0 {:p}\n    #1 {:p}", pc, pc));
+}
+
+/// Tests that the functions are callable without a sanitizer runtime.
+#[cfg(not(any(
+    sanitize = "address",
+    sanitize = "dataflow",
+    sanitize = "leak",
+    sanitize = "memory",
+    sanitize = "thread"
+)))]
+#[test]
+fn noop() {
+    common::print_stack_trace();
+    common::set_report_path("stderr");
+    common::set_report_fd(2);
+    assert!(common::get_report_path().is_none());
+    common::sandbox_on_notify(&common::SandboxArguments::default());
+    assert!(common::get_module_and_offset_for_pc(noop as fn() as *const _).is_none());
+    assert!(common::symbolize(noop as fn() as *const _).is_empty());
+    assert!(common::symbolize_global(std::ptr::null()).is_none());
+    common::set_death_callback(|| {});
+    common::clear_death_callback();
+}
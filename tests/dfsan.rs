@@ -1,12 +1,9 @@
 #![feature(cfg_sanitize)]
+#![allow(clippy::bool_assert_comparison)]
 
-#[cfg(sanitize = "dataflow")]
 use sanitizers::dfsan;
-#[cfg(sanitize = "dataflow")]
 use sanitizers::dfsan::DfsanLabel;
-#[cfg(sanitize = "dataflow")]
 use std::mem::size_of;
-#[cfg(sanitize = "dataflow")]
 use std::os::raw::c_void;
 
 /// Tests that labels are propagated through loads and stores.
@@ -19,23 +16,62 @@ fn basic() {
 
     // Set a label `i_label` on `i` using `dfsan::set_label`
     let i_label: DfsanLabel = 1;
-    dfsan::set_label(i_label, i_ptr as *mut c_void, size_of::<i64>());
+    unsafe { dfsan::set_label(i_label, i_ptr as *mut c_void, size_of::<i64>()) };
 
     // Read and verify the label using `dfsan::get_label`
     let new_label = dfsan::get_label(i);
     assert_eq!(i_label, new_label);
 
     // Read and verify the label using `dfsan::read_label`
-    let read_label = dfsan::read_label(i_ptr as *const c_void, size_of::<i64>());
+    let read_label = unsafe { dfsan::read_label(i_ptr as *const c_void, size_of::<i64>()) };
     assert_eq!(i_label, read_label);
 
     // Add a label `j_label` to `i` using `dfsan::add_label`
     let j_label: DfsanLabel = 2;
-    dfsan::add_label(j_label, i_ptr as *mut c_void, size_of::<i64>());
+    unsafe { dfsan::add_label(j_label, i_ptr as *mut c_void, size_of::<i64>()) };
 
     // Read and verify that `i` has both labels `i_label` and `j_label` using
     // `dfsan::read_label`.
-    let read_label = dfsan::read_label(i_ptr as *const c_void, size_of::<i64>());
+    let read_label = unsafe { dfsan::read_label(i_ptr as *const c_void, size_of::<i64>()) };
     assert_eq!(dfsan::has_label(read_label, i_label), true);
     assert_eq!(dfsan::has_label(read_label, j_label), true);
 }
+
+/// Tests that labels can be set on and read from slices.
+#[cfg(sanitize = "dataflow")]
+#[test]
+fn slice() {
+    let mut data = [0u8; 16];
+
+    // Set a label on the first half of the slice
+    let label: DfsanLabel = 4;
+    dfsan::set_label_val(label, &mut data[..8]);
+
+    // Read and verify the labels of both halves
+    let read_label = dfsan::read_label_val(&data[..8]);
+    assert_eq!(read_label, label);
+    let read_label = dfsan::read_label_val(&data[8..]);
+    assert_eq!(read_label, 0);
+}
+
+/// Tests that no labels are tracked when built without DataFlowSanitizer.
+#[cfg(not(sanitize = "dataflow"))]
+#[test]
+fn noop() {
+    let mut i = 1i64;
+    let i_ptr = &mut i as *mut i64;
+
+    // Setting a label has no effect
+    let i_label: DfsanLabel = 1;
+    unsafe { dfsan::set_label(i_label, i_ptr as *mut c_void, size_of::<i64>()) };
+    let new_label = dfsan::get_label(i);
+    assert_eq!(new_label, 0);
+    let read_label = unsafe { dfsan::read_label(i_ptr as *const c_void, size_of::<i64>()) };
+    assert_eq!(read_label, 0);
+
+    // Label arithmetic still works
+    let j_label: DfsanLabel = 2;
+    let union_label = dfsan::union(i_label, j_label);
+    assert_eq!(dfsan::has_label(union_label, i_label), true);
+    assert_eq!(dfsan::has_label(union_label, j_label), true);
+}
@@ -1,12 +1,18 @@
 #![feature(cfg_sanitize)]
+#![allow(clippy::bool_assert_comparison)]
 
-#[cfg(sanitize = "leak")]
+#[cfg(any(sanitize = "leak", not(sanitize = "address")))]
 use sanitizers::lsan;
 #[cfg(sanitize = "leak")]
 use std::mem::forget;
 #[cfg(sanitize = "leak")]
 use std::os::raw::c_void;
 
+// The slow unwinder is required for the allocation stack traces to include the
+// frames the suppressions are matched against.
+sanitizers::default_options!(lsan = "fast_unwind_on_malloc=0");
+sanitizers::default_suppressions!(lsan = "leak:lsan::leak_suppressed\n");
+
 /// Tests that memory regions can be ignored for leak checking.
 #[cfg(sanitize = "leak")]
 #[test]
@@ -19,10 +25,59 @@ fn basic() {
         forget(data);
 
         // Ignore the memory region for leak checking
-        lsan::ignore_object(data_ptr);
+        unsafe { lsan::ignore_object(data_ptr) };
+    })();
+
+    // Ensure no leaks are detected
+    let leaks_detected = lsan::do_recoverable_leak_check();
+    assert_eq!(leaks_detected, false);
+}
+
+/// Tests that the heap object that contains a value can be ignored for leak
+/// checking.
+#[cfg(sanitize = "leak")]
+#[test]
+fn ignore_object_val() {
+    (|| {
+        let data = Box::new([0u8; 100]);
+
+        // Ignore the allocation of the box (not the box itself) for leak
+        // checking, and forget it
+        lsan::ignore_object_val(&data[50]);
+        forget(data);
     })();
 
     // Ensure no leaks are detected
     let leaks_detected = lsan::do_recoverable_leak_check();
     assert_eq!(leaks_detected, false);
 }
+
+/// Leaks memory from a function matched by the default suppressions.
+#[cfg(sanitize = "leak")]
+#[inline(never)]
+fn leak_suppressed() {
+    forget(vec![0u8; 100]);
+}
+
+/// Tests that leaks matched by the default suppressions are not reported.
+#[cfg(sanitize = "leak")]
+#[test]
+fn default_suppressions() {
+    leak_suppressed();
+
+    // Ensure no leaks are detected
+    let leaks_detected = lsan::do_recoverable_leak_check();
+    assert_eq!(leaks_detected, false);
+}
+
+/// Tests that the interface is inert when built without LeakSanitizer.
+#[cfg(not(any(sanitize = "address", sanitize = "leak")))]
+#[test]
+fn noop() {
+    lsan::disable();
+    lsan::enable();
+
+    // No leaks are ever detected
+    let leaks_detected = lsan::do_recoverable_leak_check();
+    assert_eq!(leaks_detected, false);
+}
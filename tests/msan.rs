@@ -1,10 +1,10 @@
 #![feature(cfg_sanitize)]
 
-#[cfg(sanitize = "memory")]
 use sanitizers::msan;
-#[cfg(sanitize = "memory")]
 use std::os::raw::c_void;
 
+sanitizers::default_options!(msan = "poison_in_dtor=1");
+
 /// Tests that memory regions can be poisoned and unpoisoned.
 #[cfg(sanitize = "memory")]
 #[test]
@@ -13,16 +13,126 @@ fn msan_basic() {
     let data_ptr = data.as_mut_ptr() as *const c_void;
 
     // Poison the memory region
-    msan::poison(data_ptr, data.len());
+    unsafe { msan::poison(data_ptr, data.len()) };
 
     // Check if the memory region is poisoned
-    let poisoned_offset = msan::test_shadow(data_ptr, data.len());
+    let poisoned_offset = unsafe { msan::test_shadow(data_ptr, data.len()) };
     assert_ne!(poisoned_offset, -1);
 
     // Unpoison the memory region
-    msan::unpoison(data_ptr, data.len());
+    unsafe { msan::unpoison(data_ptr, data.len()) };
 
     // Check if the memory region is unpoisoned
-    let unpoisoned_offset = msan::test_shadow(data_ptr, data.len());
+    let unpoisoned_offset = unsafe { msan::test_shadow(data_ptr, data.len()) };
     assert_eq!(unpoisoned_offset, -1);
 }
+
+/// Tests that slices and uninitialized values can be poisoned and unpoisoned.
+#[cfg(sanitize = "memory")]
+#[test]
+fn msan_val() {
+    use std::mem::MaybeUninit;
+
+    let data = [0u32; 4];
+
+    // Poison the last element of the slice
+    msan::poison_val(&data[3..]);
+
+    // Check that the first poisoned byte is at the last element
+    let poisoned_offset = msan::test_shadow_val(&data[..]);
+    assert_eq!(poisoned_offset, Some(12));
+
+    // Unpoison the slice
+    msan::unpoison_val(&data[..]);
+    let poisoned_offset = msan::test_shadow_val(&data[..]);
+    assert_eq!(poisoned_offset, None);
+
+    // Check that an uninitialized value is poisoned until it is unpoisoned
+    let value = MaybeUninit::<u64>::uninit();
+    let poisoned_offset = msan::test_shadow_val(&value);
+    assert_eq!(poisoned_offset, Some(0));
+    msan::unpoison_val(&value);
+    let poisoned_offset = msan::test_shadow_val(&value);
+    assert_eq!(poisoned_offset, None);
+}
+
+/// Tests that death callbacks can be set and cleared.
+#[test]
+fn msan_death_callback() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let called = Arc::new(AtomicBool::new(false));
+    let callback_called = called.clone();
+    msan::set_death_callback(move || callback_called.store(true, Ordering::Relaxed));
+
+    // Check that the callback is not called without an error being reported
+    msan::clear_death_callback();
+    assert!(!called.load(Ordering::Relaxed));
+}
+
+/// Tests that death callbacks are called before the process is terminated
+/// after an error is reported, and that panics in them are contained.
+#[cfg(sanitize = "memory")]
+#[test]
+fn msan_death_callback_on_error() {
+    use std::process::Command;
+
+    // The report is fatal, so it is raised by this test in a child process.
+    if let Some(mode) = std::env::var_os("SANITIZERS_TEST_MSAN_DEATH_CALLBACK_ON_ERROR") {
+        msan::set_death_callback(|| eprintln!("replaced death callback called"));
+        if mode == "panic" {
+            msan::set_death_callback(|| panic!("death callback panicked"));
+        } else {
+            msan::set_death_callback(|| eprintln!("death callback called"));
+        }
+
+        let data = [0u8; 16];
+        msan::poison_val(&data[..]);
+        msan::check_mem_is_initialized_val(&data[..]);
+        unreachable!();
+    }
+    let run = |mode: &str| {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "msan_death_callback_on_error", "--nocapture"])
+            .env("SANITIZERS_TEST_MSAN_DEATH_CALLBACK_ON_ERROR", mode)
+            .output()
+            .unwrap();
+        // The runtime terminates the process with its exit code.
+        assert_eq!(output.status.code(), Some(77));
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    let stderr = run("print");
+    assert!(stderr.contains("WARNING: MemorySanitizer: use-of-uninitialized-value"));
+    assert!(stderr.contains("death callback called"));
+    assert!(!stderr.contains("replaced death callback called"));
+
+    // A panic in the callback does not unwind into the runtime.
+    let stderr = run("panic");
+    assert!(stderr.contains("death callback panicked"));
+    assert!(!stderr.contains("panic in a function that cannot unwind"));
+}
+
+/// Tests that the default options are defined.
+#[cfg(sanitize = "memory")]
+#[test]
+fn msan_default_options() {
+    assert_eq!(msan::default_options(), "poison_in_dtor=1");
+}
+
+/// Tests that the interface is inert when built without MemorySanitizer.
+#[cfg(not(sanitize = "memory"))]
+#[test]
+fn msan_noop() {
+    let mut data = vec![0u8; 100];
+    let data_ptr = data.as_mut_ptr() as *const c_void;
+
+    // Poisoning the memory region has no effect
+    unsafe { msan::poison(data_ptr, data.len()) };
+    let poisoned_offset = unsafe { msan::test_shadow(data_ptr, data.len()) };
+    assert_eq!(poisoned_offset, -1);
+    msan::poison_val(&data[..]);
+    let poisoned_offset = msan::test_shadow_val(&data[..]);
+    assert_eq!(poisoned_offset, None);
+}
@@ -0,0 +1,72 @@
+use sanitizers::options::{AsanOptions, LsanOptions, MsanOptions, TsanOptions};
+use std::ffi::OsStr;
+use std::process::Command;
+
+/// Tests that the options are rendered in the colon-separated form.
+#[test]
+fn display() {
+    assert_eq!(AsanOptions::new().to_string(), "");
+    assert_eq!(
+        AsanOptions::new()
+            .halt_on_error(false)
+            .detect_leaks(true)
+            .log_path("asan")
+            .to_string(),
+        "halt_on_error=0:detect_leaks=1:log_path=asan"
+    );
+    assert_eq!(
+        LsanOptions::new()
+            .exitcode(-23)
+            .extra("use_stacks=0:use_tls=0")
+            .to_string(),
+        "exitcode=-23:use_stacks=0:use_tls=0"
+    );
+    assert_eq!(
+        MsanOptions::new().poison_in_dtor(true).to_string(),
+        "poison_in_dtor=1"
+    );
+    assert_eq!(
+        TsanOptions::new()
+            .report_signal_unsafe(false)
+            .history_size(7)
+            .to_string(),
+        "report_signal_unsafe=0:history_size=7"
+    );
+}
+
+/// Tests that values containing option separators are quoted.
+#[test]
+fn display_quoted() {
+    let log_path = String::from("/tmp/my logs/asan");
+    assert_eq!(
+        AsanOptions::new().log_path(&log_path).to_string(),
+        "log_path=\"/tmp/my logs/asan\""
+    );
+    assert_eq!(
+        TsanOptions::new().suppressions("C:\"tsan\"").to_string(),
+        "suppressions='C:\"tsan\"'"
+    );
+}
+
+/// Tests that the options are rendered at compile time.
+#[test]
+fn render() {
+    const OPTIONS: AsanOptions = AsanOptions::new().malloc_context_size(30).symbolize(false);
+    const RENDERED: [u8; OPTIONS.rendered_len() + 1] = OPTIONS.render();
+    assert_eq!(&RENDERED, b"symbolize=0:malloc_context_size=30\0");
+}
+
+/// Tests that the options are set in the environment of child processes.
+#[test]
+fn apply() {
+    let mut command = Command::new("true");
+    TsanOptions::new().halt_on_error(true).apply(&mut command);
+    let envs: Vec<_> = command.get_envs().collect();
+    assert_eq!(
+        envs,
+        [(
+            OsStr::new("TSAN_OPTIONS"),
+            Some(OsStr::new("halt_on_error=1"))
+        )]
+    );
+}
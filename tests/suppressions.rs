@@ -0,0 +1,95 @@
+use sanitizers::suppressions::{Suppression, Suppressions, MAX_SUPPRESSIONS};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+sanitizers::default_suppressions!(lsan = Suppressions::new().leak("foo").leak("libbar.so"));
+
+extern "C" {
+    fn __lsan_default_suppressions() -> *const c_char;
+}
+
+/// Tests that the suppressions are rendered one per line.
+#[test]
+fn display() {
+    assert_eq!(Suppressions::new().to_string(), "");
+    assert_eq!(
+        Suppressions::new()
+            .race("foo::bar")
+            .race_top("baz")
+            .thread("spawn_*")
+            .mutex("qux")
+            .signal("handler")
+            .deadlock("lock_order")
+            .called_from_lib("libfoo.so")
+            .leak("libbar.so")
+            .to_string(),
+        "race:foo::bar\n\
+         race_top:baz\n\
+         thread:spawn_*\n\
+         mutex:qux\n\
+         signal:handler\n\
+         deadlock:lock_order\n\
+         called_from_lib:libfoo.so\n\
+         leak:libbar.so\n"
+    );
+}
+
+/// Tests that suppressions can be added to constant sets of suppressions.
+#[test]
+fn entries() {
+    const SUPPRESSIONS: Suppressions =
+        Suppressions::from_entries(&[Suppression::Race("foo"), Suppression::Leak("bar")]);
+    let suppressions = SUPPRESSIONS.mutex("baz");
+    assert_eq!(SUPPRESSIONS.entries().len(), 2);
+    assert_eq!(
+        suppressions.entries(),
+        [
+            Suppression::Race("foo"),
+            Suppression::Leak("bar"),
+            Suppression::Mutex("baz"),
+        ]
+    );
+    assert_eq!(suppressions.entries()[2].to_string(), "mutex:baz");
+}
+
+/// Tests that the suppressions are rendered at compile time.
+#[test]
+fn render() {
+    const SUPPRESSIONS: Suppressions = Suppressions::from_entries(&[
+        Suppression::Race("foo"),
+        Suppression::CalledFromLib("libbar.so"),
+    ]);
+    const RENDERED: [u8; SUPPRESSIONS.rendered_len() + 1] = SUPPRESSIONS.render();
+    assert_eq!(&RENDERED, b"race:foo\ncalled_from_lib:libbar.so\n\0");
+}
+
+/// Tests that sets of suppressions built with the builder methods are embedded
+/// in the binary.
+#[test]
+fn default_suppressions() {
+    let suppressions = unsafe { CStr::from_ptr(__lsan_default_suppressions()) };
+    assert_eq!(suppressions.to_bytes(), b"leak:foo\nleak:libbar.so\n");
+}
+
+/// Tests that sets of suppressions built in different ways are equal.
+#[test]
+fn eq() {
+    const BUILT: Suppressions = Suppressions::new().race("foo").leak("bar");
+    assert_eq!(
+        BUILT,
+        Suppressions::from_entries(&[Suppression::Race("foo"), Suppression::Leak("bar")])
+    );
+    assert_ne!(BUILT, BUILT.mutex("baz"));
+    assert_eq!(Suppressions::default(), Suppressions::new());
+}
+
+/// Tests that sets of suppressions hold at most `MAX_SUPPRESSIONS` suppressions.
+#[test]
+#[should_panic(expected = "too many suppressions")]
+fn max_suppressions() {
+    let suppressions = (0..MAX_SUPPRESSIONS).fold(Suppressions::new(), |suppressions, _| {
+        suppressions.race("foo")
+    });
+    assert_eq!(suppressions.entries().len(), MAX_SUPPRESSIONS);
+    suppressions.race("foo");
+}
@@ -5,6 +5,12 @@ use sanitizers::tsan;
 #[cfg(sanitize = "thread")]
 use std::os::raw::c_void;
 
+sanitizers::default_suppressions!(
+    tsan = sanitizers::suppressions::Suppressions::new()
+        .called_from_lib("libfoo.so")
+        .race("bar::baz")
+);
+
 /// Tests that memory regions can be locked and unlocked.
 #[cfg(sanitize = "thread")]
 #[test]
@@ -13,11 +19,21 @@ fn tsan_basic() {
     let data_ptr = data.as_mut_ptr() as *mut c_void;
 
     // Acquire a lock
-    tsan::acquire(data_ptr);
+    unsafe { tsan::acquire(data_ptr) };
 
     // Release the lock
-    tsan::release(data_ptr);
+    unsafe { tsan::release(data_ptr) };
 
     // Check that no mutexes are held
     tsan::check_no_mutexes_held();
 }
+
+/// Tests that the default suppressions are embedded in the binary.
+#[cfg(sanitize = "thread")]
+#[test]
+fn tsan_default_suppressions() {
+    assert_eq!(
+        tsan::default_suppressions(),
+        "called_from_lib:libfoo.so\nrace:bar::baz\n"
+    );
+}